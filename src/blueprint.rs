@@ -0,0 +1,500 @@
+use std::fs;
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputSystem,
+    },
+    prelude::*,
+    utils::HashMap,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    build_queue::BuildQueue,
+    constants::{BLUEPRINTS_PATH, BLUEPRINT_NAME_MAX, GHOST_LIFT, MINIMAP_CELL, WORLD_SIZE},
+    game::GameState,
+    input_map::{ActionInput, InputAction, InputMap},
+    locale::Locale,
+    preview::Ghost,
+    select_tile::{Selected, TileType},
+    util::{Category, Notification, NotifyQueue},
+    world::{Hovered, Position},
+};
+
+pub struct BlueprintPlugin;
+
+impl Plugin for BlueprintPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Blueprints::load())
+            .init_resource::<Pasting>()
+            .init_resource::<NamingBlueprint>()
+            .add_systems(Startup, (make_paste_tooltip, make_name_prompt))
+            .add_systems(
+                PreUpdate,
+                type_blueprint_name
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    save_blueprint,
+                    update_name_prompt,
+                    choose_blueprint,
+                    (update_paste_ghosts, update_paste_tooltip, stamp_blueprint),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Built tiles relative to the blueprint's lowest corner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blueprint {
+    pub name: String,
+    pub tiles: Vec<(IVec2, TileType)>,
+}
+
+/// Saved blueprints, loaded from and saved to [`BLUEPRINTS_PATH`].
+#[derive(Resource, Debug, Default)]
+pub struct Blueprints(Vec<Blueprint>);
+
+impl Blueprints {
+    fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(BLUEPRINTS_PATH) else {
+            return Self::default();
+        };
+        match ron::from_str(&contents) {
+            Ok(blueprints) => Self(blueprints),
+            Err(err) => {
+                warn!(
+                    "Ignoring invalid blueprints in {}: {}",
+                    BLUEPRINTS_PATH, err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(&self.0, default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = std::path::Path::new(BLUEPRINTS_PATH).parent() {
+                    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+                }
+                fs::write(BLUEPRINTS_PATH, contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Failed to save blueprints: {}", err);
+        }
+    }
+}
+
+/// A new blueprint waiting for the player to name it.
+#[derive(Resource, Debug, Default)]
+pub struct NamingBlueprint(Option<Blueprint>);
+
+#[derive(Component)]
+pub struct NamePrompt;
+
+/// Index of the blueprint being placed, if any.
+#[derive(Resource, Debug, Default)]
+pub struct Pasting(Option<usize>);
+
+/// Ghost of a tile a blueprint would build, kept apart from action preview ghosts.
+#[derive(Component)]
+pub struct BlueprintGhost;
+
+#[derive(Component)]
+pub struct PasteTooltip;
+
+type TileQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Position,
+        &'static TileType,
+        &'static Transform,
+        &'static Selected,
+        Has<Hovered>,
+    ),
+>;
+
+/// Where a blueprint lands: each tile to build with its target and cost.
+struct Placement {
+    builds: Vec<(Entity, TileType, Vec3, usize)>,
+    blocked: usize,
+}
+
+fn grid(position: &Position) -> IVec2 {
+    IVec2::new(position.0.x as i32, position.0.z as i32)
+}
+
+fn make_paste_tooltip(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle::default())
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                // Above the minimap
+                bottom: Val::Px(MINIMAP_CELL * WORLD_SIZE as f32 + 16.0),
+                right: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        PasteTooltip,
+    ));
+}
+
+fn make_name_prompt(mut commands: Commands) {
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section("", TextStyle::default())
+        }
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(40.0),
+            left: Val::Percent(35.0),
+            padding: UiRect::all(Val::Px(12.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        NamePrompt,
+    ));
+}
+
+/// Captures the selected farms and towers as a new blueprint, then asks for its name.
+fn save_blueprint(
+    inputs: ActionInput,
+    blueprints: Res<Blueprints>,
+    mut naming: ResMut<NamingBlueprint>,
+    q_tiles: Query<(&Selected, &Position, &TileType)>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+) {
+    if !inputs.just_pressed(InputAction::SaveBlueprint) {
+        return;
+    }
+    let tiles = q_tiles
+        .iter()
+        .filter(|(sel, _, tile_type)| sel.0 && (tile_type.is_tile() || tile_type.is_farm()))
+        .map(|(_, position, tile_type)| (grid(position), tile_type.clone()))
+        .collect::<Vec<_>>();
+    let Some(origin) = tiles.iter().map(|(pos, _)| *pos).reduce(IVec2::min) else {
+        notify_queue.push(
+            Notification::new(
+                Category::Construction,
+                locale.t("blueprint-empty-selection"),
+            )
+            .warning(),
+        );
+        return;
+    };
+    naming.0 = Some(Blueprint {
+        name: locale.fmt(
+            "blueprint-default-name",
+            &[("number", &locale.number(blueprints.0.len() + 1))],
+        ),
+        tiles: tiles
+            .into_iter()
+            .map(|(pos, tile_type)| (pos - origin, tile_type))
+            .collect(),
+    });
+}
+
+/// Edits the name of the blueprint being saved. Enter saves it, Escape drops it.
+fn type_blueprint_name(
+    mut keyboard: EventReader<KeyboardInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut naming: ResMut<NamingBlueprint>,
+    mut blueprints: ResMut<Blueprints>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    // Read every frame, so the key that opened the prompt isn't typed into it
+    let typed = keyboard
+        .read()
+        .filter(|ev| ev.state == ButtonState::Pressed)
+        .map(|ev| ev.logical_key.clone())
+        .collect::<Vec<_>>();
+    if naming.0.is_none() {
+        return;
+    }
+    // Keep the typing from triggering hotkeys
+    keys.reset_all();
+    if typed.is_empty() {
+        return;
+    }
+    let Some(mut blueprint) = naming.0.take() else {
+        return;
+    };
+    for key in typed {
+        match key {
+            Key::Character(text) => blueprint
+                .name
+                .extend(text.chars().filter(|c| !c.is_control())),
+            Key::Space => blueprint.name.push(' '),
+            Key::Backspace => {
+                blueprint.name.pop();
+            }
+            Key::Escape => return,
+            Key::Enter if !blueprint.name.trim().is_empty() => {
+                blueprint.name = blueprint.name.trim().to_owned();
+                notify_queue.push(Notification::new(
+                    Category::Construction,
+                    locale.fmt(
+                        "blueprint-saved",
+                        &[
+                            ("name", &blueprint.name),
+                            ("count", &locale.number(blueprint.tiles.len())),
+                        ],
+                    ),
+                ));
+                blueprints.0.push(blueprint);
+                blueprints.save();
+                return;
+            }
+            _ => {}
+        }
+    }
+    blueprint.name = blueprint.name.chars().take(BLUEPRINT_NAME_MAX).collect();
+    naming.0 = Some(blueprint);
+}
+
+fn update_name_prompt(
+    naming: Res<NamingBlueprint>,
+    locale: Res<Locale>,
+    mut q_prompt: Query<(&mut Text, &mut Visibility), With<NamePrompt>>,
+) {
+    if !naming.is_changed() && !locale.is_changed() {
+        return;
+    }
+    let Ok((mut text, mut visibility)) = q_prompt.get_single_mut() else {
+        return;
+    };
+    let Some(blueprint) = &naming.0 else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    text.sections[0].value = locale.fmt(
+        "blueprint-name-prompt",
+        &[
+            ("count", &locale.number(blueprint.tiles.len())),
+            ("name", &blueprint.name),
+        ],
+    );
+}
+
+/// Starts placing the newest blueprint, cycles to the next on repeat, cancels on right click.
+fn choose_blueprint(
+    inputs: ActionInput,
+    mouse: Res<ButtonInput<MouseButton>>,
+    blueprints: Res<Blueprints>,
+    mut pasting: ResMut<Pasting>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    if pasting.0.is_some() && mouse.just_pressed(MouseButton::Right) {
+        pasting.0 = None;
+        return;
+    }
+    if !inputs.just_pressed(InputAction::PasteBlueprint) {
+        return;
+    }
+    if blueprints.0.is_empty() {
+        notify_queue.push(
+            Notification::new(Category::Construction, locale.t("blueprint-none-saved")).warning(),
+        );
+        return;
+    }
+    pasting.0 = Some(match pasting.0 {
+        Some(index) => (index + 1) % blueprints.0.len(),
+        None => blueprints.0.len() - 1,
+    });
+}
+
+/// Lays the blueprint out from the hovered tile, or the selected one without a mouse.
+fn place(blueprint: &Blueprint, q_tiles: &TileQuery) -> Option<Placement> {
+    let anchor = q_tiles
+        .iter()
+        .find(|(.., hovered)| *hovered)
+        .or_else(|| q_tiles.iter().find(|(.., sel, _)| sel.0))
+        .map(|(_, position, ..)| grid(position))?;
+    let tiles = q_tiles
+        .iter()
+        .map(|(entity, position, tile_type, transform, ..)| {
+            (grid(position), (entity, tile_type, transform.translation))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut placement = Placement {
+        builds: vec![],
+        blocked: 0,
+    };
+    blueprint.tiles.iter().for_each(|(offset, target)| {
+        let Some((entity, tile_type, translation)) = tiles.get(&(anchor + *offset)) else {
+            placement.blocked += 1;
+            return;
+        };
+        match tile_type.actions_to(target) {
+            Some(actions) if actions.is_empty() => {}
+            Some(actions) => placement.builds.push((
+                *entity,
+                target.clone(),
+                *translation,
+                tile_type.actions_cost(&actions),
+            )),
+            None => placement.blocked += 1,
+        }
+    });
+    Some(placement)
+}
+
+#[allow(clippy::type_complexity)]
+fn update_paste_ghosts(
+    mut commands: Commands,
+    pasting: Res<Pasting>,
+    blueprints: Res<Blueprints>,
+    asset_server: Res<AssetServer>,
+    q_changed: Query<(), Or<(Added<Hovered>, Changed<Selected>, Changed<TileType>)>>,
+    q_tiles: TileQuery,
+    q_ghosts: Query<Entity, With<BlueprintGhost>>,
+) {
+    if !pasting.is_changed() && q_changed.is_empty() {
+        return;
+    }
+    q_ghosts
+        .iter()
+        .for_each(|ghost| commands.entity(ghost).despawn_recursive());
+    let Some(placement) = pasting
+        .0
+        .and_then(|index| blueprints.0.get(index))
+        .and_then(|blueprint| place(blueprint, &q_tiles))
+    else {
+        return;
+    };
+    placement
+        .builds
+        .iter()
+        .for_each(|(_, target, translation, _)| {
+            commands.spawn((
+                SceneBundle {
+                    scene: asset_server.load(target.texture()),
+                    transform: Transform::from_translation(*translation + Vec3::Y * GHOST_LIFT),
+                    ..default()
+                },
+                Ghost,
+                BlueprintGhost,
+            ));
+        })
+}
+
+fn update_paste_tooltip(
+    pasting: Res<Pasting>,
+    blueprints: Res<Blueprints>,
+    input_map: Res<InputMap>,
+    locale: Res<Locale>,
+    q_tiles: TileQuery,
+    mut q_tooltip: Query<(&mut Text, &mut Visibility), With<PasteTooltip>>,
+) {
+    let Ok((mut text, mut visibility)) = q_tooltip.get_single_mut() else {
+        return;
+    };
+    let Some(blueprint) = pasting.0.and_then(|index| blueprints.0.get(index)) else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let key = |action| {
+        input_map
+            .key(action)
+            .map_or(locale.t("key-unbound"), |key| format!("{:?}", key))
+    };
+    let mut lines = vec![locale.fmt(
+        "blueprint-title",
+        &[
+            ("name", &blueprint.name),
+            ("count", &locale.number(blueprint.tiles.len())),
+        ],
+    )];
+    match place(blueprint, &q_tiles) {
+        Some(placement) => {
+            let cost = placement
+                .builds
+                .iter()
+                .map(|(.., cost)| cost)
+                .sum::<usize>();
+            lines.push(locale.fmt(
+                "blueprint-cost",
+                &[
+                    ("count", &locale.number(placement.builds.len())),
+                    ("amount", &locale.number(cost)),
+                ],
+            ));
+            if placement.blocked > 0 {
+                lines.push(locale.fmt(
+                    "blueprint-blocked",
+                    &[("count", &locale.number(placement.blocked))],
+                ));
+            }
+        }
+        None => lines.push(locale.t("blueprint-hover")),
+    }
+    lines.push(locale.fmt(
+        "blueprint-controls",
+        &[
+            ("confirm", &key(InputAction::Confirm)),
+            ("next", &key(InputAction::PasteBlueprint)),
+        ],
+    ));
+    text.sections[0].value = lines.join("\n");
+}
+
+/// Queues every build of the placed blueprint.
+fn stamp_blueprint(
+    inputs: ActionInput,
+    mut pasting: ResMut<Pasting>,
+    blueprints: Res<Blueprints>,
+    mut queue: ResMut<BuildQueue>,
+    q_tiles: TileQuery,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    // Confirm applies the pending action while previewing instead
+    if !inputs.just_pressed(InputAction::Confirm) || inputs.pressed(InputAction::Preview) {
+        return;
+    }
+    let Some(blueprint) = pasting.0.and_then(|index| blueprints.0.get(index)) else {
+        return;
+    };
+    let Some(placement) = place(blueprint, &q_tiles) else {
+        return;
+    };
+    // Merged with builds already queued on the same tiles, so pasting twice doesn't double up
+    let (count, cost) = placement
+        .builds
+        .into_iter()
+        .filter(|(tile, target, ..)| queue.merge(*tile, target.clone()))
+        .fold((0, 0), |(count, total), (.., cost)| {
+            (count + 1, total + cost)
+        });
+    notify_queue.push(Notification::new(
+        Category::Construction,
+        locale.fmt(
+            "blueprint-queued",
+            &[
+                ("count", &locale.number(count)),
+                ("name", &blueprint.name),
+                ("amount", &locale.number(cost)),
+            ],
+        ),
+    ));
+    pasting.0 = None;
+}
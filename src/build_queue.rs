@@ -0,0 +1,322 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{
+    currency::{collect_towers, Currency, Ledger, Payout},
+    game::GameState,
+    history::History,
+    locale::Locale,
+    research::Research,
+    select_tile::{apply_action, sync_tile_visuals, Action, Invested, Selected, TileType},
+    util::{Category, Notification, NotifyQueue},
+    world::Position,
+};
+
+pub struct BuildQueuePlugin;
+
+impl Plugin for BuildQueuePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BuildQueue>()
+            .add_event::<QueueRequest>()
+            .add_systems(Startup, make_queue_ui)
+            .add_systems(
+                Update,
+                (
+                    queue_selected.run_if(on_event::<QueueRequest>()),
+                    reorder_queue,
+                    process_build_queue
+                        .run_if(on_event::<Payout>().or_else(resource_changed::<BuildQueue>))
+                        .after(collect_towers)
+                        .before(sync_tile_visuals),
+                    update_queue_ui,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// A tile to build up to `target`, one action at a time.
+#[derive(Debug, Clone)]
+pub struct QueuedBuild {
+    pub tile: Entity,
+    pub target: TileType,
+}
+
+/// Builds waiting for funds, bought in order.
+#[derive(Resource, Debug, Default)]
+pub struct BuildQueue(pub VecDeque<QueuedBuild>);
+
+impl BuildQueue {
+    /// Queues building `tile` up to `target`. A build already queued there is retargeted instead
+    /// of queued twice, unless that would undo some of it. Returns whether `target` is queued.
+    pub fn merge(&mut self, tile: Entity, target: TileType) -> bool {
+        match self.0.iter_mut().find(|build| build.tile == tile) {
+            Some(build) if build.target.actions_to(&target).is_some() => {
+                build.target = target;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.0.push_back(QueuedBuild { tile, target });
+                true
+            }
+        }
+    }
+}
+
+/// Queues an action on every selected tile instead of applying it now.
+#[derive(Event, Debug)]
+pub struct QueueRequest(pub Action);
+
+#[derive(Component)]
+pub struct QueuePanel;
+
+#[derive(Debug, Clone, Copy)]
+pub enum QueueOp {
+    Up,
+    Down,
+    Remove,
+}
+
+#[derive(Component)]
+pub struct QueueButton {
+    index: usize,
+    op: QueueOp,
+}
+
+fn make_queue_ui(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(8.0),
+                top: Val::Px(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        QueuePanel,
+        Interaction::default(),
+    ));
+}
+
+/// Adds the action's result to the queue, building on top of anything already queued there.
+fn queue_selected(
+    mut requests: EventReader<QueueRequest>,
+    mut queue: ResMut<BuildQueue>,
+    q_selected: Query<(Entity, &Selected, &TileType)>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    for QueueRequest(action) in requests.read() {
+        if *action == Action::Sell {
+            notify_queue.push(
+                Notification::new(Category::Construction, locale.t("queue-sell-refused")).warning(),
+            );
+            continue;
+        }
+        let mut count = 0;
+        q_selected
+            .iter()
+            .filter(|(_, sel, _)| sel.0)
+            .for_each(|(tile, _, tile_type)| {
+                let base = queue
+                    .0
+                    .iter()
+                    .find(|build| build.tile == tile)
+                    .map_or(tile_type, |build| &build.target);
+                let Some(target) = base.after_action(action) else {
+                    return;
+                };
+                if queue.merge(tile, target) {
+                    count += 1;
+                }
+            });
+        notify_queue.push(Notification::new(
+            Category::Construction,
+            locale.fmt(
+                "queue-added",
+                &[
+                    ("action", &locale.t(action.key())),
+                    ("count", &locale.number(count)),
+                ],
+            ),
+        ));
+    }
+}
+
+fn reorder_queue(
+    mut queue: ResMut<BuildQueue>,
+    q_buttons: Query<(&Interaction, &QueueButton), Changed<Interaction>>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(_, QueueButton { index, op })| {
+            let len = queue.0.len();
+            match op {
+                QueueOp::Up if *index > 0 && *index < len => queue.0.swap(*index, index - 1),
+                QueueOp::Down if index + 1 < len => queue.0.swap(*index, index + 1),
+                QueueOp::Remove if *index < len => {
+                    queue.0.remove(*index);
+                }
+                _ => {}
+            }
+        })
+}
+
+/// Buys queued builds from the front until one can't be afforded.
+#[allow(clippy::too_many_arguments)]
+fn process_build_queue(
+    mut queue: ResMut<BuildQueue>,
+    mut q_tiles: Query<(&mut TileType, &mut Invested)>,
+    research: Res<Research>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    mut history: ResMut<History>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    let mut changes = vec![];
+    while let Some(build) = queue.0.front().cloned() {
+        // Read only until the build is bought, so waiting for funds doesn't mark the tile changed
+        let Ok((tile_type, _)) = q_tiles.get(build.tile) else {
+            queue.0.pop_front();
+            continue;
+        };
+        let Some(action) = tile_type
+            .actions_to(&build.target)
+            .and_then(|actions| actions.into_iter().next())
+        else {
+            // Finished, or the tile was changed into something that can't reach the target
+            queue.0.pop_front();
+            continue;
+        };
+        if tile_type.available_action(&action, &research).is_none() {
+            notify_queue.push(
+                Notification::new(
+                    Category::Construction,
+                    locale.fmt(
+                        "queue-dropped",
+                        &[("tile", &build.target.localized(&locale))],
+                    ),
+                )
+                .warning()
+                .with_source(build.tile),
+            );
+            queue.0.pop_front();
+            continue;
+        }
+        if currency.wood < tile_type.get_action_cost(&action) {
+            break;
+        }
+        let Ok((mut tile_type, mut invested)) = q_tiles.get_mut(build.tile) else {
+            break;
+        };
+        match apply_action(
+            build.tile,
+            &mut tile_type,
+            &mut invested,
+            &action,
+            &research,
+            &mut currency,
+            &mut ledger,
+        ) {
+            Some(change) => changes.push(change),
+            None => break,
+        }
+    }
+    history.record(changes);
+}
+
+/// Lists queued builds with their remaining cost, rebuilt whenever the queue or a tile changes.
+fn update_queue_ui(
+    mut commands: Commands,
+    queue: Res<BuildQueue>,
+    q_changed: Query<(), Changed<TileType>>,
+    q_tiles: Query<(&TileType, &Position)>,
+    mut q_panel: Query<(Entity, &mut Visibility), With<QueuePanel>>,
+    locale: Res<Locale>,
+) {
+    if !queue.is_changed() && q_changed.is_empty() && !locale.is_changed() {
+        return;
+    }
+    let Ok((panel, mut visibility)) = q_panel.get_single_mut() else {
+        return;
+    };
+    *visibility = match queue.0.is_empty() {
+        true => Visibility::Hidden,
+        false => Visibility::Inherited,
+    };
+    commands.entity(panel).despawn_descendants();
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            locale.t("queue-title"),
+            TextStyle::default(),
+        ));
+        queue.0.iter().enumerate().for_each(|(index, build)| {
+            let Ok((tile_type, position)) = q_tiles.get(build.tile) else {
+                return;
+            };
+            let cost = tile_type
+                .actions_to(&build.target)
+                .map_or(0, |actions| tile_type.actions_cost(&actions));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(4.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.spawn(TextBundle::from_section(
+                        locale.fmt(
+                            "queue-entry",
+                            &[
+                                ("index", &locale.number(index + 1)),
+                                ("tile", &build.target.localized(&locale)),
+                                ("x", &locale.number(position.0.x)),
+                                ("z", &locale.number(position.0.z)),
+                                ("amount", &locale.number(cost)),
+                            ],
+                        ),
+                        TextStyle::default(),
+                    ));
+                    [
+                        (QueueOp::Up, "queue-up"),
+                        (QueueOp::Down, "queue-down"),
+                        (QueueOp::Remove, "queue-remove"),
+                    ]
+                    .into_iter()
+                    .for_each(|(op, key)| {
+                        row.spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::horizontal(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                                ..default()
+                            },
+                            QueueButton { index, op },
+                        ))
+                        .with_children(|button| {
+                            button.spawn(TextBundle::from_section(
+                                locale.t(key),
+                                TextStyle::default(),
+                            ));
+                        });
+                    });
+                });
+        });
+    });
+}
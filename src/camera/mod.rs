@@ -1,5 +1,11 @@
 use bevy::prelude::*;
 
+use crate::{
+    game::GameState,
+    input_map::{ActionInput, InputAction, InputMap},
+    select_tile::BoxSelect,
+};
+
 pub struct CameraControllerPlugin;
 
 impl Plugin for CameraControllerPlugin {
@@ -7,8 +13,12 @@ impl Plugin for CameraControllerPlugin {
         app.add_systems(Startup, spawn_camera).add_systems(
             Update,
             (
-                pan_orbit_camera.run_if(any_with_component::<PanOrbitState>),
-                debug_camera,
+                apply_camera_bindings.run_if(resource_changed::<InputMap>),
+                (
+                    pan_orbit_camera.run_if(any_with_component::<PanOrbitState>),
+                    debug_camera,
+                )
+                    .run_if(in_state(GameState::Playing)),
             ),
         );
     }
@@ -39,12 +49,16 @@ pub struct PanOrbitSettings {
     pub pan_sensitivity: f32,
     pub orbit_sensitivity: f32,
     pub zoom_sensitivity: f32,
-    pub pan_key: Option<KeyCode>,
-    pub orbit_key: Option<KeyCode>,
-    pub zoom_key: Option<KeyCode>,
+    pub pan_keys: Vec<KeyCode>,
+    pub orbit_keys: Vec<KeyCode>,
+    pub zoom_keys: Vec<KeyCode>,
     pub scroll_action: Option<PanOrbitAction>,
     pub scroll_line_sensitivity: f32,
     pub scroll_pixel_sensitivity: f32,
+    /// Per second at full stick or trigger deflection.
+    pub gamepad_pan_sensitivity: f32,
+    pub gamepad_orbit_sensitivity: f32,
+    pub gamepad_zoom_sensitivity: f32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -72,12 +86,15 @@ impl Default for PanOrbitSettings {
             pan_sensitivity: 0.001,
             orbit_sensitivity: 0.1f32.to_radians(),
             zoom_sensitivity: 0.01,
-            pan_key: Some(KeyCode::ControlLeft),
-            orbit_key: Some(KeyCode::AltLeft),
-            zoom_key: Some(KeyCode::ShiftLeft),
+            pan_keys: vec![KeyCode::ControlLeft],
+            orbit_keys: vec![KeyCode::AltLeft],
+            zoom_keys: vec![KeyCode::ShiftLeft],
             scroll_action: Some(PanOrbitAction::Zoom),
             scroll_line_sensitivity: 16.0,
             scroll_pixel_sensitivity: 1.0,
+            gamepad_pan_sensitivity: 1.0,
+            gamepad_orbit_sensitivity: 120.0f32.to_radians(),
+            gamepad_zoom_sensitivity: 1.0,
         }
     }
 }
@@ -95,13 +112,60 @@ use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
 
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
+/// Stick and shoulder button input summed over all connected gamepads.
+fn gamepad_motion(
+    gamepads: &Gamepads,
+    axes: &Axis<GamepadAxis>,
+    buttons: &ButtonInput<GamepadButton>,
+) -> (Vec2, Vec2, f32) {
+    gamepads.iter().fold(
+        (Vec2::ZERO, Vec2::ZERO, 0.0),
+        |(left, right, zoom), gamepad| {
+            let axis = |axis_type| {
+                axes.get(GamepadAxis::new(gamepad, axis_type))
+                    .unwrap_or(0.0)
+            };
+            let button = |button_type| {
+                buttons.pressed(GamepadButton::new(gamepad, button_type)) as u8 as f32
+            };
+            (
+                left + Vec2::new(
+                    axis(GamepadAxisType::LeftStickX),
+                    axis(GamepadAxisType::LeftStickY),
+                ),
+                right
+                    + Vec2::new(
+                        axis(GamepadAxisType::RightStickX),
+                        axis(GamepadAxisType::RightStickY),
+                    ),
+                zoom + button(GamepadButtonType::RightTrigger)
+                    - button(GamepadButtonType::LeftTrigger),
+            )
+        },
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 fn pan_orbit_camera(
     kbd: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    time: Res<Time>,
+    box_select: Res<BoxSelect>,
     mut evr_motion: EventReader<MouseMotion>,
     mut evr_scroll: EventReader<MouseWheel>,
     mut q_camera: Query<(&PanOrbitSettings, &mut PanOrbitState, &mut Transform)>,
 ) {
+    let (left_stick, right_stick, trigger_zoom) =
+        gamepad_motion(&gamepads, &axes, &gamepad_buttons);
+    let dt = time.delta_seconds();
+
     let mut total_motion: Vec2 = evr_motion.read().map(|ev| ev.delta).sum();
+    // Shift adds to the selection too, so a box selection doesn't move the camera
+    if box_select.active() {
+        total_motion = Vec2::ZERO;
+    }
     total_motion.y = -total_motion.y;
 
     let mut total_scroll_lines = Vec2::ZERO;
@@ -121,11 +185,7 @@ fn pan_orbit_camera(
 
     for (settings, mut state, mut transform) in &mut q_camera {
         let mut total_pan = Vec2::ZERO;
-        if settings
-            .pan_key
-            .map(|key| kbd.pressed(key))
-            .unwrap_or(false)
-        {
+        if kbd.any_pressed(settings.pan_keys.iter().copied()) {
             total_pan -= total_motion * settings.pan_sensitivity;
         }
         if settings.scroll_action == Some(PanOrbitAction::Pan) {
@@ -134,13 +194,10 @@ fn pan_orbit_camera(
             total_pan -=
                 total_scroll_pixels * settings.scroll_pixel_sensitivity * settings.pan_sensitivity;
         }
+        total_pan += left_stick * settings.gamepad_pan_sensitivity * dt;
 
         let mut total_orbit = Vec2::ZERO;
-        if settings
-            .orbit_key
-            .map(|key| kbd.pressed(key))
-            .unwrap_or(false)
-        {
+        if kbd.any_pressed(settings.orbit_keys.iter().copied()) {
             total_orbit -= total_motion * settings.orbit_sensitivity;
         }
         if settings.scroll_action == Some(PanOrbitAction::Orbit) {
@@ -150,13 +207,10 @@ fn pan_orbit_camera(
                 * settings.scroll_pixel_sensitivity
                 * settings.orbit_sensitivity;
         }
+        total_orbit -= right_stick * settings.gamepad_orbit_sensitivity * dt;
 
         let mut total_zoom = Vec2::ZERO;
-        if settings
-            .zoom_key
-            .map(|key| kbd.pressed(key))
-            .unwrap_or(false)
-        {
+        if kbd.any_pressed(settings.zoom_keys.iter().copied()) {
             total_zoom -= total_motion * settings.zoom_sensitivity;
         }
         if settings.scroll_action == Some(PanOrbitAction::Zoom) {
@@ -165,12 +219,9 @@ fn pan_orbit_camera(
             total_zoom -=
                 total_scroll_pixels * settings.scroll_pixel_sensitivity * settings.zoom_sensitivity;
         }
+        total_zoom.y += trigger_zoom * settings.gamepad_zoom_sensitivity * dt;
 
-        if settings
-            .orbit_key
-            .map(|key| kbd.just_pressed(key))
-            .unwrap_or(false)
-        {
+        if kbd.any_just_pressed(settings.orbit_keys.iter().copied()) {
             state.upside_down = state.pitch < -FRAC_PI_2 || state.pitch > FRAC_PI_2;
         }
 
@@ -217,8 +268,16 @@ fn pan_orbit_camera(
     }
 }
 
-fn debug_camera(keys: Res<ButtonInput<KeyCode>>, camera: Query<&PanOrbitState, With<GameCamera>>) {
-    if !keys.just_pressed(KeyCode::KeyD) {
+fn apply_camera_bindings(input_map: Res<InputMap>, mut q_settings: Query<&mut PanOrbitSettings>) {
+    q_settings.iter_mut().for_each(|mut settings| {
+        settings.pan_keys = input_map.keys(InputAction::Pan).to_vec();
+        settings.orbit_keys = input_map.keys(InputAction::Orbit).to_vec();
+        settings.zoom_keys = input_map.keys(InputAction::Zoom).to_vec();
+    })
+}
+
+fn debug_camera(inputs: ActionInput, camera: Query<&PanOrbitState, With<GameCamera>>) {
+    if !inputs.just_pressed(InputAction::DebugCamera) {
         return;
     }
     camera.iter().for_each(|camera| {
@@ -6,28 +6,74 @@ use crate::select_tile::{ResourceType, TileType};
 
 pub const WINDOW_WIDTH: f32 = 800.0;
 pub const WINDOW_HEIGHT: f32 = 512.0;
+pub const WINDOW_TITLE: &str = "3D TD";
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (WINDOW_WIDTH, WINDOW_HEIGHT),
+    (1280.0, 720.0),
+    (1600.0, 900.0),
+    (1920.0, 1080.0),
+];
+pub const UI_SCALES: [f32; 4] = [0.75, 1.0, 1.25, 1.5];
+pub const SETTINGS_PATH: &str = "config/settings.ron";
+pub const BINDINGS_PATH: &str = "config/bindings.ron";
+pub const BLUEPRINTS_PATH: &str = "config/blueprints.ron";
+pub const BLUEPRINT_NAME_MAX: usize = 24;
+pub const REPORTS_DIR: &str = "reports";
+pub const INTEREST_PATH: &str = "assets/interest.ron";
 pub const TOWER_TS: f32 = 2.0;
 pub const COST_SCALING: usize = 5;
 pub const TILE_COST_SCALING: usize = 5;
 pub const FARM_COST_SCALING: usize = 5;
 pub const FARM_PRODUCTION: usize = 5;
+pub const TOWER_RANGE: f32 = 32.0;
+pub const TOWER_RANGE_PER_LEVEL: f32 = 8.0;
+pub const TOWER_UPKEEP: usize = 1;
 pub const MODIFY_PENALTY: f32 = 1.5;
+pub const SELL_RATIO: f32 = 0.5;
+pub const BASE_TOWER_LEVEL_CAP: usize = 3;
+pub const BASE_FARM_LEVEL_CAP: usize = 3;
+pub const INTEREST_RATE: f32 = 0.1;
+pub const INTEREST_CAP: usize = 20;
+pub const BUILD_PHASE_DURATION: f32 = 30.0;
+pub const WAVE_DURATION: f32 = 20.0;
+pub const CALL_EARLY_BONUS: f32 = 0.5;
+pub const MARKET_LOT: usize = 10;
+pub const MARKET_IMPACT: f32 = 0.05;
+pub const MARKET_RECOVERY: f32 = 0.02;
+pub const MARKET_MIN_PRICE: f32 = 0.1;
+pub const MARKET_SETTLED: f32 = 0.001;
 pub const WORLD_SIZE: usize = 20;
 pub const TILE_SIZE: f32 = 8.0;
+pub const TILE_SPACING: f32 = 2.0 * TILE_SIZE;
+pub const BOX_SELECT_THRESHOLD: f32 = 8.0;
+pub const GHOST_ALPHA: f32 = 0.4;
+pub const GHOST_LIFT: f32 = 0.5;
+pub const POPUP_HEIGHT: f32 = 12.0;
+pub const POPUP_RISE: f32 = 8.0;
+pub const POPUP_DURATION: f32 = 1.5;
+pub const RADIAL_MENU_RADIUS: f32 = 70.0;
+pub const RADIAL_BUTTON_WIDTH: f32 = 140.0;
+pub const TOAST_DURATION: f32 = 4.0;
+pub const TOAST_MAX_VISIBLE: usize = 5;
+pub const MINIMAP_CELL: f32 = 8.0;
 
 lazy_static! {
     pub static ref TEXTURE_MAP: HashMap<TileType, String> = izip![
         vec![
             TileType::Floor,
             TileType::Resource(ResourceType::Wood),
+            TileType::Resource(ResourceType::Stone),
             TileType::Tile(1.into()),
-            TileType::Farm(1.into(), ResourceType::Wood)
+            TileType::Farm(1.into(), ResourceType::Wood),
+            TileType::Farm(1.into(), ResourceType::Stone),
         ],
         vec![
             "floor.gltf#Scene0".to_owned(),
             "res_wood.gltf#Scene0".to_owned(),
+            "res_stone.gltf#Scene0".to_owned(),
             "tower.gltf#Scene0".to_owned(),
             "farm_wood.gltf#Scene0".to_owned(),
+            "farm_stone.gltf#Scene0".to_owned(),
         ],
     ]
     .collect::<HashMap<_, _>>();
@@ -0,0 +1,238 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::GameCamera,
+    constants::{RADIAL_BUTTON_WIDTH, RADIAL_MENU_RADIUS},
+    currency::Currency,
+    game::GameState,
+    locale::Locale,
+    research::Research,
+    select_tile::{Action, ActionRequest, Invested, Selected, TileType},
+    world::tile_center,
+};
+
+pub struct ContextMenuPlugin;
+
+impl Plugin for ContextMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<OpenContextMenu>().add_systems(
+            Update,
+            (
+                close_menu,
+                open_menu.run_if(on_event::<OpenContextMenu>()),
+                (press_menu_button, position_menu, update_menu_buttons),
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Opens the radial menu around a tile.
+#[derive(Event, Debug)]
+pub struct OpenContextMenu(pub Entity);
+
+/// Radial menu listing the actions valid for `tile`.
+#[derive(Component)]
+pub struct RadialMenu {
+    tile: Entity,
+}
+
+#[derive(Component)]
+pub struct RadialMenuButton(Action);
+
+fn open_menu(
+    mut commands: Commands,
+    mut events: EventReader<OpenContextMenu>,
+    research: Res<Research>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&TileType, &Invested)>,
+    q_menus: Query<Entity, With<RadialMenu>>,
+) {
+    let Some(OpenContextMenu(tile)) = events.read().last() else {
+        return;
+    };
+    q_menus
+        .iter()
+        .for_each(|menu| commands.entity(menu).despawn_recursive());
+    let Ok((tile_type, invested)) = q_tiles.get(*tile) else {
+        return;
+    };
+    let actions = Action::ALL
+        .into_iter()
+        .filter(|action| tile_type.available_action(action, &research).is_some())
+        .collect::<Vec<_>>();
+    if actions.is_empty() {
+        return;
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            RadialMenu { tile: *tile },
+        ))
+        .with_children(|parent| {
+            let step = TAU / actions.len() as f32;
+            actions.into_iter().enumerate().for_each(|(i, action)| {
+                // First entry at the top, going clockwise
+                let angle = i as f32 * step - FRAC_PI_2;
+                let price = match action {
+                    Action::Sell => locale.fmt(
+                        "price-refund",
+                        &[("amount", &locale.number(invested.sell_value()))],
+                    ),
+                    _ => locale.fmt(
+                        "price-cost",
+                        &[("amount", &locale.number(tile_type.get_action_cost(&action)))],
+                    ),
+                };
+                let label = locale.fmt(
+                    "action-entry",
+                    &[("action", &locale.t(action.key())), ("price", &price)],
+                );
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                left: Val::Px(
+                                    angle.cos() * RADIAL_MENU_RADIUS - RADIAL_BUTTON_WIDTH / 2.0,
+                                ),
+                                top: Val::Px(angle.sin() * RADIAL_MENU_RADIUS),
+                                width: Val::Px(RADIAL_BUTTON_WIDTH),
+                                padding: UiRect::all(Val::Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                            ..default()
+                        },
+                        RadialMenuButton(action),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(label, TextStyle::default()));
+                    });
+            });
+        });
+}
+
+/// Closes the menu on right click, once its tile is deselected or after any action.
+fn close_menu(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut requests: EventReader<ActionRequest>,
+    q_selected: Query<&Selected>,
+    q_menus: Query<(Entity, &RadialMenu)>,
+) {
+    let dismissed = mouse.just_pressed(MouseButton::Right) || requests.read().count() > 0;
+    q_menus
+        .iter()
+        .filter(|(_, menu)| dismissed || !q_selected.get(menu.tile).is_ok_and(|sel| sel.0))
+        .for_each(|(entity, _)| commands.entity(entity).despawn_recursive());
+}
+
+fn position_menu(
+    q_camera: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    q_tiles: Query<&GlobalTransform>,
+    mut q_menus: Query<(&RadialMenu, &mut Style, &mut Visibility)>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+    q_menus
+        .iter_mut()
+        .for_each(|(menu, mut style, mut visibility)| {
+            let Some(viewport) = q_tiles.get(menu.tile).ok().and_then(|transform| {
+                camera.world_to_viewport(camera_transform, tile_center(transform.translation()))
+            }) else {
+                *visibility = Visibility::Hidden;
+                return;
+            };
+            *visibility = Visibility::Inherited;
+            style.left = Val::Px(viewport.x);
+            style.top = Val::Px(viewport.y);
+        })
+}
+
+fn menu_tile_type<'a>(
+    entity: Entity,
+    q_parents: &Query<&Parent>,
+    q_menus: &Query<&RadialMenu>,
+    q_tiles: &'a Query<&TileType>,
+) -> Option<&'a TileType> {
+    let menu = q_menus.get(q_parents.get(entity).ok()?.get()).ok()?;
+    q_tiles.get(menu.tile).ok()
+}
+
+/// Greys out entries the player can't afford, writing only when that changes.
+fn update_menu_buttons(
+    currency: Res<Currency>,
+    q_parents: Query<&Parent>,
+    q_menus: Query<&RadialMenu>,
+    q_tiles: Query<&TileType>,
+    mut q_buttons: Query<(Entity, &RadialMenuButton, &mut BackgroundColor, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    q_buttons.iter_mut().for_each(
+        |(entity, RadialMenuButton(action), mut background, children)| {
+            let affordable = menu_tile_type(entity, &q_parents, &q_menus, &q_tiles)
+                .is_some_and(|tile_type| currency.wood >= tile_type.get_action_cost(action));
+            let (fill, color) = match affordable {
+                true => (Color::srgb(0.2, 0.2, 0.2), Color::WHITE),
+                false => (Color::srgb(0.1, 0.1, 0.1), Color::srgb(0.5, 0.5, 0.5)),
+            };
+            if background.0 != fill {
+                background.0 = fill;
+            }
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                if text.sections[0].style.color != color {
+                    text.sections[0].style.color = color;
+                }
+            }
+        },
+    )
+}
+
+fn press_menu_button(
+    mut commands: Commands,
+    currency: Res<Currency>,
+    mut requests: EventWriter<ActionRequest>,
+    q_parents: Query<&Parent>,
+    q_menus: Query<&RadialMenu>,
+    q_tiles: Query<&TileType>,
+    q_buttons: Query<(Entity, &Interaction, &RadialMenuButton), Changed<Interaction>>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(_, interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(entity, _, RadialMenuButton(action))| {
+            let affordable = menu_tile_type(entity, &q_parents, &q_menus, &q_tiles)
+                .is_some_and(|tile_type| currency.wood >= tile_type.get_action_cost(action));
+            if !affordable {
+                return;
+            }
+            let Some((menu, RadialMenu { tile })) = q_parents
+                .get(entity)
+                .ok()
+                .and_then(|parent| Some((parent.get(), q_menus.get(parent.get()).ok()?)))
+            else {
+                return;
+            };
+            // Only the tile the menu was opened on, not the rest of the selection
+            requests.send(ActionRequest {
+                action: action.clone(),
+                tile: Some(*tile),
+            });
+            commands.entity(menu).despawn_recursive();
+        })
+}
@@ -1,9 +1,18 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use itertools::iproduct;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{FARM_PRODUCTION, TOWER_TS},
-    select_tile::TileType,
-    ui::CurrencyCounter,
+    constants::{INTEREST_CAP, INTEREST_PATH, INTEREST_RATE, POPUP_HEIGHT, TOWER_TS},
+    game::GameState,
+    input_map::{ActionInput, InputAction},
+    locale::Locale,
+    research::Research,
+    select_tile::{ResourceType, TileType},
+    ui::{spawn_floating_text, CurrencyCounter},
+    util::{Category, Notification, NotifyQueue},
+    wave::BuildPhaseEnded,
+    world::Position,
 };
 
 pub struct CurrencyPlugin;
@@ -11,44 +20,412 @@ pub struct CurrencyPlugin;
 impl Plugin for CurrencyPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(Currency::default())
-            .insert_resource(TowerTimer(Timer::from_seconds(
-                TOWER_TS,
-                TimerMode::Repeating,
-            )))
-            .add_systems(Update, (collect_towers, update_ui));
+            .add_event::<Payout>()
+            .insert_resource(InterestConfig::load())
+            .init_resource::<Ledger>()
+            .add_systems(
+                Update,
+                (
+                    (track_production_timers, collect_towers).chain(),
+                    pay_interest.run_if(on_event::<BuildPhaseEnded>()),
+                    update_ui,
+                    debug_ledger,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 #[derive(Resource)]
 pub struct Currency {
     pub wood: usize,
+    pub stone: usize,
 }
 
 impl Default for Currency {
     fn default() -> Self {
-        Self { wood: 5 }
+        Self { wood: 5, stone: 0 }
     }
 }
 
-#[derive(Resource)]
-pub struct TowerTimer(Timer);
+impl Currency {
+    pub fn get(&self, resource: ResourceType) -> usize {
+        match resource {
+            ResourceType::Wood => self.wood,
+            ResourceType::Stone => self.stone,
+        }
+    }
+
+    pub fn get_mut(&mut self, resource: ResourceType) -> &mut usize {
+        match resource {
+            ResourceType::Wood => &mut self.wood,
+            ResourceType::Stone => &mut self.stone,
+        }
+    }
+}
+
+/// Interest paid on banked currency when a build phase ends, tuned in [`INTEREST_PATH`].
+///
+/// Fields left out of the file keep their defaults.
+#[derive(Resource, Debug, Deserialize)]
+#[serde(default)]
+pub struct InterestConfig {
+    pub enabled: bool,
+    pub rate: f32,
+    pub cap: usize,
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rate: INTEREST_RATE,
+            cap: INTEREST_CAP,
+        }
+    }
+}
+
+impl InterestConfig {
+    /// Read when the game starts, so the file can be tuned without rebuilding.
+    fn load() -> Self {
+        let Ok(contents) = std::fs::read_to_string(INTEREST_PATH) else {
+            warn!("No interest config at {}, using defaults", INTEREST_PATH);
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!(
+                "Ignoring invalid interest config in {}: {}",
+                INTEREST_PATH, err
+            );
+            Self::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum LedgerCategory {
+    Production,
+    Upkeep,
+    Interest,
+    Construction,
+    Sales,
+    Market,
+    Research,
+    EarlyCall,
+}
+
+impl LedgerCategory {
+    pub const ALL: [LedgerCategory; 8] = [
+        LedgerCategory::Production,
+        LedgerCategory::Upkeep,
+        LedgerCategory::Interest,
+        LedgerCategory::Construction,
+        LedgerCategory::Sales,
+        LedgerCategory::Market,
+        LedgerCategory::Research,
+        LedgerCategory::EarlyCall,
+    ];
+
+    /// Message catalog key for the category's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            LedgerCategory::Production => "ledger-production",
+            LedgerCategory::Upkeep => "ledger-upkeep",
+            LedgerCategory::Interest => "ledger-interest",
+            LedgerCategory::Construction => "ledger-construction",
+            LedgerCategory::Sales => "ledger-sales",
+            LedgerCategory::Market => "ledger-market",
+            LedgerCategory::Research => "ledger-research",
+            LedgerCategory::EarlyCall => "ledger-early-call",
+        }
+    }
+}
+
+/// Running totals of every currency change, by category and resource. Gains and losses are
+/// also summed separately, so a category that nets out to zero still shows its activity.
+#[derive(Resource, Debug, Default)]
+pub struct Ledger {
+    totals: HashMap<(LedgerCategory, ResourceType), isize>,
+    earned: HashMap<(LedgerCategory, ResourceType), usize>,
+    spent: HashMap<(LedgerCategory, ResourceType), usize>,
+}
+
+impl Ledger {
+    pub fn record(&mut self, category: LedgerCategory, resource: ResourceType, amount: isize) {
+        if amount == 0 {
+            return;
+        }
+        *self.totals.entry((category, resource)).or_default() += amount;
+        let side = match amount > 0 {
+            true => &mut self.earned,
+            false => &mut self.spent,
+        };
+        *side.entry((category, resource)).or_default() += amount.unsigned_abs();
+    }
+
+    pub fn total(&self, category: LedgerCategory, resource: ResourceType) -> isize {
+        self.totals.get(&(category, resource)).copied().unwrap_or(0)
+    }
+
+    pub fn earned(&self, category: LedgerCategory, resource: ResourceType) -> usize {
+        self.earned.get(&(category, resource)).copied().unwrap_or(0)
+    }
+
+    pub fn spent(&self, category: LedgerCategory, resource: ResourceType) -> usize {
+        self.spent.get(&(category, resource)).copied().unwrap_or(0)
+    }
+}
+
+/// Per-tile economy tick, started when the farm or tower is built so payouts are staggered.
+#[derive(Component)]
+pub struct ProductionTimer(Timer);
+
+impl Default for ProductionTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(TOWER_TS, TimerMode::Repeating))
+    }
+}
+
+/// A tower whose last upkeep couldn't be paid. It stays dormant until an upkeep tick succeeds.
+#[derive(Component)]
+pub struct Dormant;
 
-fn collect_towers(
-    query: Query<&TileType>,
+fn track_production_timers(
+    mut commands: Commands,
+    query: Query<(Entity, &TileType, Has<ProductionTimer>), Changed<TileType>>,
+) {
+    query.iter().for_each(|(entity, tile_type, has_timer)| {
+        let mut entity = commands.entity(entity);
+        match (
+            tile_type.farm().is_some() || tile_type.tile().is_some(),
+            has_timer,
+        ) {
+            (true, false) => {
+                entity.insert(ProductionTimer::default());
+            }
+            (false, true) => {
+                entity.remove::<ProductionTimer>();
+            }
+            _ => {}
+        }
+        if tile_type.tile().is_none() {
+            entity.remove::<Dormant>();
+        }
+    })
+}
+
+/// Sent once per frame in which any farm paid out.
+#[derive(Event, Debug)]
+pub struct Payout;
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub(crate) fn collect_towers(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &TileType,
+        &Transform,
+        &Position,
+        &mut ProductionTimer,
+        Has<Dormant>,
+    )>,
+    research: Res<Research>,
     mut currency: ResMut<Currency>,
-    mut tower_timer: ResMut<TowerTimer>,
+    mut ledger: ResMut<Ledger>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    mut payouts: EventWriter<Payout>,
+    locale: Res<Locale>,
     time: Res<Time>,
 ) {
-    if !tower_timer.0.tick(time.delta()).just_finished() {
-        return;
+    let mut paid = false;
+    query
+        .iter_mut()
+        .filter_map(
+            |(entity, tile_type, transform, position, mut timer, dormant)| {
+                timer
+                    .0
+                    .tick(time.delta())
+                    .just_finished()
+                    .then_some((entity, tile_type, transform, position, dormant))
+            },
+        )
+        .for_each(|(entity, tile_type, transform, position, dormant)| {
+            let popup_origin = transform.translation + Vec3::Y * POPUP_HEIGHT;
+
+            if let Some((resource, income)) = tile_type.production() {
+                let income = research.farm_yield(income);
+                *currency.get_mut(resource) += income;
+                ledger.record(LedgerCategory::Production, resource, income as isize);
+                paid = true;
+                spawn_floating_text(
+                    &mut commands,
+                    locale.fmt(
+                        "popup-income",
+                        &[
+                            ("amount", &locale.number(income)),
+                            ("resource", &locale.t(resource.key())),
+                        ],
+                    ),
+                    popup_origin,
+                );
+            }
+
+            let upkeep = tile_type.upkeep();
+            if upkeep == 0 {
+                return;
+            }
+            if currency.wood < upkeep {
+                if !dormant {
+                    commands.entity(entity).insert(Dormant);
+                    notify_queue.push(
+                        Notification::new(
+                            Category::Economy,
+                            locale.fmt(
+                                "economy-dormant",
+                                &[
+                                    ("x", &locale.number(position.0.x)),
+                                    ("z", &locale.number(position.0.z)),
+                                ],
+                            ),
+                        )
+                        .warning()
+                        .with_source(entity)
+                        .at(transform.translation),
+                    );
+                }
+                return;
+            }
+            currency.wood -= upkeep;
+            ledger.record(
+                LedgerCategory::Upkeep,
+                ResourceType::Wood,
+                -(upkeep as isize),
+            );
+            spawn_floating_text(
+                &mut commands,
+                locale.fmt("popup-upkeep", &[("amount", &locale.number(upkeep))]),
+                popup_origin,
+            );
+            if dormant {
+                commands.entity(entity).remove::<Dormant>();
+                notify_queue.push(
+                    Notification::new(
+                        Category::Economy,
+                        locale.fmt(
+                            "economy-reactivated",
+                            &[
+                                ("x", &locale.number(position.0.x)),
+                                ("z", &locale.number(position.0.z)),
+                            ],
+                        ),
+                    )
+                    .with_source(entity)
+                    .at(transform.translation),
+                );
+            }
+        });
+    if paid {
+        payouts.send(Payout);
     }
-    query.iter().filter_map(|l| l.farm()).for_each(|farm| {
-        currency.wood += FARM_PRODUCTION * farm;
-    })
 }
 
-fn update_ui(currency: Res<Currency>, mut query: Query<&mut Text, With<CurrencyCounter>>) {
+fn pay_interest(
+    mut build_phase_ended: EventReader<BuildPhaseEnded>,
+    config: Res<InterestConfig>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+) {
+    build_phase_ended
+        .read()
+        .filter(|_| config.enabled)
+        .for_each(|ev| {
+            let interest = ((currency.wood as f32 * config.rate) as usize).min(config.cap);
+            if interest == 0 {
+                return;
+            }
+            currency.wood += interest;
+            ledger.record(
+                LedgerCategory::Interest,
+                ResourceType::Wood,
+                interest as isize,
+            );
+            notify_queue.push(Notification::new(
+                Category::Economy,
+                locale.fmt(
+                    "economy-interest",
+                    &[
+                        ("amount", &locale.number(interest)),
+                        ("wave", &locale.number(ev.wave)),
+                    ],
+                ),
+            ));
+        })
+}
+
+fn update_ui(
+    currency: Res<Currency>,
+    research: Res<Research>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&TileType, Has<Dormant>)>,
+    mut query: Query<&mut Text, With<CurrencyCounter>>,
+) {
     let mut counter = query.single_mut();
 
-    *counter = Text::from_section(format!("Money: {}", currency.wood), default());
+    let mut net = HashMap::<ResourceType, isize>::new();
+    let mut dormant = 0;
+    q_tiles.iter().for_each(|(tile_type, is_dormant)| {
+        if is_dormant {
+            dormant += 1;
+            return;
+        }
+        if let Some((resource, income)) = tile_type.production() {
+            *net.entry(resource).or_default() += research.farm_yield(income) as isize;
+        }
+        *net.entry(ResourceType::Wood).or_default() -= tile_type.upkeep() as isize;
+    });
+
+    let balances = ResourceType::ALL
+        .iter()
+        .map(|resource| {
+            locale.fmt(
+                "currency-balance",
+                &[
+                    ("amount", &locale.number(currency.get(*resource))),
+                    ("resource", &locale.t(resource.key())),
+                    (
+                        "net",
+                        &locale.signed(net.get(resource).copied().unwrap_or(0)),
+                    ),
+                ],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut text = locale.fmt(
+        "currency-counter",
+        &[
+            ("balances", &balances),
+            ("period", &locale.number(TOWER_TS)),
+        ],
+    );
+    if dormant > 0 {
+        text += &locale.fmt("currency-dormant", &[("count", &locale.number(dormant))]);
+    }
+    *counter = Text::from_section(text, default());
+}
+
+fn debug_ledger(inputs: ActionInput, ledger: Res<Ledger>) {
+    if !inputs.just_pressed(InputAction::DebugLedger) {
+        return;
+    }
+    iproduct!(LedgerCategory::ALL, ResourceType::ALL).for_each(|(category, resource)| {
+        debug!(
+            "{:?} {}: {}",
+            category,
+            resource.name(),
+            ledger.total(category, resource)
+        );
+    })
 }
@@ -3,38 +3,83 @@ use bevy_mod_picking::DefaultPickingPlugins;
 use bevy_rapier3d::prelude::*;
 
 use crate::{
+    blueprint::BlueprintPlugin,
+    build_queue::BuildQueuePlugin,
     camera::CameraControllerPlugin,
-    constants::{WINDOW_HEIGHT, WINDOW_WIDTH},
+    context_menu::ContextMenuPlugin,
     currency::CurrencyPlugin,
+    history::HistoryPlugin,
+    input_map::InputMapPlugin,
+    inspector::InspectorPlugin,
+    locale::LocalePlugin,
+    market::MarketPlugin,
+    menu::MenuPlugin,
+    minimap::MinimapPlugin,
+    preview::PreviewPlugin,
+    research::ResearchPlugin,
     select_tile::SelectTilePlugin,
+    settings::{Settings, SettingsPlugin},
+    stats::StatsPlugin,
     ui::UIPlugin,
     util::NotifyPlugin,
+    wave::WavePlugin,
     world::WorldPlugin,
 };
 
 pub struct GamePlugin;
 
+/// Which screen the app is on. Gameplay systems only run while [`GameState::Playing`].
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    #[default]
+    MainMenu,
+    Loading,
+    Playing,
+    Paused,
+    Settings,
+    /// End of game statistics, after which the world is cleared.
+    Summary,
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        // Loaded before the window is created, so it opens with the saved size and mode
+        let settings = Settings::load();
         app.add_plugins((
             DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    title: "3D TD".to_string(),
-                    resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
-                    resizable: false,
-                    ..default()
-                }),
+                primary_window: Some(settings.window()),
                 ..default()
             }),
-            WorldPlugin,
-            CameraControllerPlugin,
-            SelectTilePlugin,
             DefaultPickingPlugins,
             RapierPhysicsPlugin::<NoUserData>::default(),
             RapierDebugRenderPlugin::default(),
+        ))
+        .init_state::<GameState>()
+        .insert_resource(settings)
+        .add_plugins((
+            WorldPlugin,
+            CameraControllerPlugin,
+            SelectTilePlugin,
+            BlueprintPlugin,
+            BuildQueuePlugin,
             CurrencyPlugin,
-            UIPlugin,
+            HistoryPlugin,
+            InputMapPlugin,
+            MarketPlugin,
+            ResearchPlugin,
             NotifyPlugin,
+            WavePlugin,
+        ))
+        .add_plugins((
+            ContextMenuPlugin,
+            InspectorPlugin,
+            LocalePlugin,
+            MenuPlugin,
+            MinimapPlugin,
+            PreviewPlugin,
+            SettingsPlugin,
+            StatsPlugin,
+            UIPlugin,
         ));
     }
 }
@@ -0,0 +1,171 @@
+use bevy::prelude::*;
+
+use crate::{
+    currency::{Currency, Ledger, LedgerCategory},
+    game::GameState,
+    input_map::{ActionInput, InputAction},
+    locale::Locale,
+    select_tile::{sync_tile_visuals, Invested, ResourceType, TileType},
+    util::{Category, Notification, NotifyQueue},
+    wave::{Phase, WaveState},
+};
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<History>().add_systems(
+            Update,
+            (reset_history, undo_redo)
+                .chain()
+                .before(sync_tile_visuals)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// One tile changed by an action, with what the action cost and refunded.
+#[derive(Debug, Clone)]
+pub struct TileChange {
+    pub entity: Entity,
+    pub before: TileType,
+    pub after: TileType,
+    pub cost: usize,
+    pub refund: usize,
+    pub invested_before: usize,
+    pub invested_after: usize,
+}
+
+impl TileChange {
+    /// Wood gained by reverting this change, negative if reverting costs wood.
+    fn revert_gain(&self) -> isize {
+        self.cost as isize - self.refund as isize
+    }
+}
+
+/// Actions applied during the current build phase. Each entry is one action on every tile it
+/// changed, so a bulk action is undone as a whole.
+#[derive(Resource, Debug, Default)]
+pub struct History {
+    undo: Vec<Vec<TileChange>>,
+    redo: Vec<Vec<TileChange>>,
+    phase: Option<Phase>,
+}
+
+impl History {
+    pub fn record(&mut self, changes: Vec<TileChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.undo.push(changes);
+        self.redo.clear();
+    }
+}
+
+/// Undo never reaches back past the start of the current phase.
+fn reset_history(wave_state: Res<WaveState>, mut history: ResMut<History>) {
+    if history.phase == Some(wave_state.phase) {
+        return;
+    }
+    history.phase = Some(wave_state.phase);
+    history.undo.clear();
+    history.redo.clear();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn undo_redo(
+    inputs: ActionInput,
+    wave_state: Res<WaveState>,
+    mut history: ResMut<History>,
+    mut q_tiles: Query<(&mut TileType, &mut Invested)>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    if !inputs.pressed(InputAction::UndoModifier) {
+        return;
+    }
+    let undo = inputs.just_pressed(InputAction::Undo);
+    if !undo && !inputs.just_pressed(InputAction::Redo) {
+        return;
+    }
+    if wave_state.phase != Phase::Build {
+        notify_queue.push(
+            Notification::new(Category::Construction, locale.t("history-build-phase-only"))
+                .warning(),
+        );
+        return;
+    }
+
+    let history = history.as_mut();
+    let (from, to) = match undo {
+        true => (&mut history.undo, &mut history.redo),
+        false => (&mut history.redo, &mut history.undo),
+    };
+    let Some(changes) = from.pop() else {
+        return;
+    };
+
+    // Undoing a sale takes the refund back, redoing an upgrade pays for it again
+    let gain = changes
+        .iter()
+        .map(|change| match undo {
+            true => change.revert_gain(),
+            false => -change.revert_gain(),
+        })
+        .sum::<isize>();
+    let unchanged = changes.iter().all(|change| {
+        let expected = match undo {
+            true => &change.after,
+            false => &change.before,
+        };
+        q_tiles
+            .get(change.entity)
+            .is_ok_and(|(tile, _)| *tile == *expected)
+    });
+    if !unchanged {
+        // The tiles were changed some other way, so the rest of the stack is stale too
+        from.clear();
+        return;
+    }
+    if (currency.wood as isize) + gain < 0 {
+        notify_queue
+            .push(Notification::new(Category::Construction, locale.t("history-short")).warning());
+        from.push(changes);
+        return;
+    }
+
+    changes.iter().for_each(|change| {
+        if let Ok((mut tile, mut invested)) = q_tiles.get_mut(change.entity) {
+            (*tile, invested.0) = match undo {
+                true => (change.before.clone(), change.invested_before),
+                false => (change.after.clone(), change.invested_after),
+            };
+        }
+        let sign = if undo { 1 } else { -1 };
+        ledger.record(
+            LedgerCategory::Construction,
+            ResourceType::Wood,
+            sign * change.cost as isize,
+        );
+        ledger.record(
+            LedgerCategory::Sales,
+            ResourceType::Wood,
+            -sign * change.refund as isize,
+        );
+    });
+    currency.wood = (currency.wood as isize + gain) as usize;
+    notify_queue.push(Notification::new(
+        Category::Construction,
+        locale.fmt(
+            if undo {
+                "history-undid"
+            } else {
+                "history-redid"
+            },
+            &[("count", &locale.number(changes.len()))],
+        ),
+    ));
+    to.push(changes);
+}
@@ -0,0 +1,552 @@
+use std::fs;
+
+use bevy::{ecs::system::SystemParam, input::InputSystem, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::BINDINGS_PATH,
+    locale::Locale,
+    util::{Category, Notification, NotifyQueue},
+};
+
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputMap::load())
+            .init_resource::<Rebinding>()
+            .add_systems(Startup, make_bindings_ui)
+            .add_systems(PreUpdate, capture_rebind.after(InputSystem))
+            .add_systems(
+                Update,
+                (toggle_bindings_ui, start_rebind, update_bindings_ui),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    MakeFarm,
+    MakeTile,
+    Sell,
+    AddToSelection,
+    Preview,
+    Confirm,
+    Undo,
+    Redo,
+    UndoModifier,
+    ShowRanges,
+    QueueAction,
+    SaveBlueprint,
+    PasteBlueprint,
+    NavUp,
+    NavDown,
+    NavLeft,
+    NavRight,
+    Pan,
+    Orbit,
+    Zoom,
+    ToggleResearch,
+    ToggleBindings,
+    DebugCamera,
+    DebugLedger,
+    Pause,
+}
+
+impl InputAction {
+    pub const ALL: [InputAction; 25] = [
+        InputAction::MakeFarm,
+        InputAction::MakeTile,
+        InputAction::Sell,
+        InputAction::AddToSelection,
+        InputAction::Preview,
+        InputAction::Confirm,
+        InputAction::Undo,
+        InputAction::Redo,
+        InputAction::UndoModifier,
+        InputAction::ShowRanges,
+        InputAction::QueueAction,
+        InputAction::SaveBlueprint,
+        InputAction::PasteBlueprint,
+        InputAction::NavUp,
+        InputAction::NavDown,
+        InputAction::NavLeft,
+        InputAction::NavRight,
+        InputAction::Pan,
+        InputAction::Orbit,
+        InputAction::Zoom,
+        InputAction::ToggleResearch,
+        InputAction::ToggleBindings,
+        InputAction::DebugCamera,
+        InputAction::DebugLedger,
+        InputAction::Pause,
+    ];
+
+    /// Held with mouse motion to move the camera, and does nothing on its own.
+    fn moves_camera(&self) -> bool {
+        matches!(
+            self,
+            InputAction::Pan | InputAction::Orbit | InputAction::Zoom
+        )
+    }
+
+    /// Held to change what a click or another key does.
+    fn is_modifier(&self) -> bool {
+        matches!(
+            self,
+            InputAction::AddToSelection | InputAction::UndoModifier
+        )
+    }
+
+    /// Whether both actions can be bound to the same key. A camera action only acts while the
+    /// mouse moves, so it can share a modifier key, like Ctrl for panning and Ctrl+Z.
+    fn can_share(&self, other: InputAction) -> bool {
+        (self.moves_camera() && other.is_modifier()) || (self.is_modifier() && other.moves_camera())
+    }
+
+    /// Message catalog key for the action's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            InputAction::MakeFarm => "input-make-farm",
+            InputAction::MakeTile => "input-make-tile",
+            InputAction::Sell => "input-sell",
+            InputAction::AddToSelection => "input-add-to-selection",
+            InputAction::Preview => "input-preview",
+            InputAction::Confirm => "input-confirm",
+            InputAction::Undo => "input-undo",
+            InputAction::Redo => "input-redo",
+            InputAction::UndoModifier => "input-undo-modifier",
+            InputAction::ShowRanges => "input-show-ranges",
+            InputAction::QueueAction => "input-queue-action",
+            InputAction::SaveBlueprint => "input-save-blueprint",
+            InputAction::PasteBlueprint => "input-paste-blueprint",
+            InputAction::NavUp => "input-nav-up",
+            InputAction::NavDown => "input-nav-down",
+            InputAction::NavLeft => "input-nav-left",
+            InputAction::NavRight => "input-nav-right",
+            InputAction::Pan => "input-pan",
+            InputAction::Orbit => "input-orbit",
+            InputAction::Zoom => "input-zoom",
+            InputAction::ToggleResearch => "input-toggle-research",
+            InputAction::ToggleBindings => "input-toggle-bindings",
+            InputAction::DebugCamera => "input-debug-camera",
+            InputAction::DebugLedger => "input-debug-ledger",
+            InputAction::Pause => "input-pause",
+        }
+    }
+}
+
+/// Keys and gamepad button bound to each [`InputAction`], loaded from and saved to
+/// [`BINDINGS_PATH`]. Any of an action's keys triggers it. Gamepad buttons and extra keys can
+/// only be bound in the file.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct InputMap {
+    bindings: HashMap<InputAction, Vec<KeyCode>>,
+    #[serde(default)]
+    gamepad: HashMap<InputAction, GamepadButtonType>,
+}
+
+/// Bindings files written before an action could have more than one key.
+#[derive(Deserialize)]
+struct LegacyInputMap {
+    bindings: HashMap<InputAction, KeyCode>,
+    #[serde(default)]
+    gamepad: HashMap<InputAction, GamepadButtonType>,
+}
+
+impl From<LegacyInputMap> for InputMap {
+    fn from(legacy: LegacyInputMap) -> Self {
+        Self {
+            bindings: legacy
+                .bindings
+                .into_iter()
+                .map(|(action, key)| (action, vec![key]))
+                .collect(),
+            gamepad: legacy.gamepad,
+        }
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self {
+            bindings: [
+                (InputAction::MakeFarm, vec![KeyCode::KeyF]),
+                (InputAction::MakeTile, vec![KeyCode::KeyU]),
+                (InputAction::Sell, vec![KeyCode::KeyX]),
+                (
+                    InputAction::AddToSelection,
+                    vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+                ),
+                (InputAction::Preview, vec![KeyCode::Tab]),
+                (InputAction::Confirm, vec![KeyCode::Enter]),
+                (InputAction::Undo, vec![KeyCode::KeyZ]),
+                (InputAction::Redo, vec![KeyCode::KeyY]),
+                (
+                    InputAction::UndoModifier,
+                    vec![KeyCode::ControlLeft, KeyCode::ControlRight],
+                ),
+                (InputAction::ShowRanges, vec![KeyCode::KeyH]),
+                (InputAction::QueueAction, vec![KeyCode::KeyQ]),
+                (InputAction::SaveBlueprint, vec![KeyCode::KeyB]),
+                (InputAction::PasteBlueprint, vec![KeyCode::KeyV]),
+                (InputAction::NavUp, vec![KeyCode::ArrowUp]),
+                (InputAction::NavDown, vec![KeyCode::ArrowDown]),
+                (InputAction::NavLeft, vec![KeyCode::ArrowLeft]),
+                (InputAction::NavRight, vec![KeyCode::ArrowRight]),
+                (InputAction::Pan, vec![KeyCode::ControlLeft]),
+                (InputAction::Orbit, vec![KeyCode::AltLeft]),
+                (InputAction::Zoom, vec![KeyCode::ShiftLeft]),
+                (InputAction::ToggleResearch, vec![KeyCode::KeyR]),
+                (InputAction::ToggleBindings, vec![KeyCode::F1]),
+                (InputAction::DebugCamera, vec![KeyCode::F3]),
+                (InputAction::DebugLedger, vec![KeyCode::F4]),
+                (InputAction::Pause, vec![KeyCode::Escape]),
+            ]
+            .into_iter()
+            .collect(),
+            gamepad: [
+                (InputAction::MakeFarm, GamepadButtonType::West),
+                (InputAction::MakeTile, GamepadButtonType::North),
+                (InputAction::Sell, GamepadButtonType::East),
+                (InputAction::Confirm, GamepadButtonType::South),
+                (InputAction::Preview, GamepadButtonType::LeftTrigger2),
+                (
+                    InputAction::AddToSelection,
+                    GamepadButtonType::RightTrigger2,
+                ),
+                (InputAction::NavUp, GamepadButtonType::DPadUp),
+                (InputAction::NavDown, GamepadButtonType::DPadDown),
+                (InputAction::NavLeft, GamepadButtonType::DPadLeft),
+                (InputAction::NavRight, GamepadButtonType::DPadRight),
+                (InputAction::Pause, GamepadButtonType::Start),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl InputMap {
+    fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(BINDINGS_PATH) else {
+            return Self::default();
+        };
+        let parsed = ron::from_str::<InputMap>(&contents).or_else(|err| {
+            ron::from_str::<LegacyInputMap>(&contents)
+                .map(InputMap::from)
+                .map_err(|_| err)
+        });
+        match parsed {
+            Ok(mut input_map) => {
+                // Fill in actions added since the file was written, leaving out keys the player
+                // has since given to something else
+                let defaults = Self::default();
+                defaults.bindings.into_iter().for_each(|(action, keys)| {
+                    if input_map.bindings.contains_key(&action) {
+                        return;
+                    }
+                    let keys = keys
+                        .into_iter()
+                        .filter(|key| !input_map.clashes(action, *key))
+                        .collect::<Vec<_>>();
+                    if !keys.is_empty() {
+                        input_map.bindings.insert(action, keys);
+                    }
+                });
+                defaults.gamepad.into_iter().for_each(|(action, button)| {
+                    if !input_map.gamepad.values().any(|bound| *bound == button) {
+                        input_map.gamepad.entry(action).or_insert(button);
+                    }
+                });
+                input_map.conflicts().iter().for_each(|(a, b)| {
+                    warn!("{:?} and {:?} share a key binding", a, b);
+                });
+                input_map
+            }
+            Err(err) => {
+                warn!(
+                    "Ignoring invalid key bindings in {}: {}",
+                    BINDINGS_PATH, err
+                );
+                Self::default()
+            }
+        }
+    }
+
+    fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = std::path::Path::new(BINDINGS_PATH).parent() {
+                    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+                }
+                fs::write(BINDINGS_PATH, contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Failed to save key bindings: {}", err);
+        }
+    }
+
+    /// The first key bound to `action`, to show in hints.
+    pub fn key(&self, action: InputAction) -> Option<KeyCode> {
+        self.keys(action).first().copied()
+    }
+
+    pub fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn button(&self, action: InputAction) -> Option<GamepadButtonType> {
+        self.gamepad.get(&action).copied()
+    }
+
+    /// Whether `key` is already bound to an action that can't share it with `action`.
+    fn clashes(&self, action: InputAction, key: KeyCode) -> bool {
+        InputAction::ALL.into_iter().any(|other| {
+            other != action && !action.can_share(other) && self.keys(other).contains(&key)
+        })
+    }
+
+    /// Every pair of actions bound to the same key or gamepad button that can't share it.
+    pub fn conflicts(&self) -> Vec<(InputAction, InputAction)> {
+        InputAction::ALL
+            .iter()
+            .enumerate()
+            .flat_map(|(i, a)| InputAction::ALL[i + 1..].iter().map(move |b| (*a, *b)))
+            .filter(|(a, b)| {
+                (!a.can_share(*b) && self.keys(*a).iter().any(|key| self.keys(*b).contains(key)))
+                    || (self.button(*a).is_some() && self.button(*a) == self.button(*b))
+            })
+            .collect()
+    }
+}
+
+/// Keyboard and gamepad state, read through the [`InputMap`].
+#[derive(SystemParam)]
+pub struct ActionInput<'w> {
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    input_map: Res<'w, InputMap>,
+}
+
+impl ActionInput<'_> {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.input_map
+            .keys(action)
+            .iter()
+            .any(|key| self.keys.pressed(*key))
+            || self.input_map.button(action).is_some_and(|button| {
+                self.buttons
+                    .get_pressed()
+                    .any(|pressed| pressed.button_type == button)
+            })
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.input_map
+            .keys(action)
+            .iter()
+            .any(|key| self.keys.just_pressed(*key))
+            || self.input_map.button(action).is_some_and(|button| {
+                self.buttons
+                    .get_just_pressed()
+                    .any(|pressed| pressed.button_type == button)
+            })
+    }
+
+    /// Every action whose key or button went down this frame.
+    pub fn all_just_pressed(&self) -> impl Iterator<Item = InputAction> + '_ {
+        InputAction::ALL
+            .into_iter()
+            .filter(|action| self.just_pressed(*action))
+    }
+}
+
+/// The action waiting for a new key, while the bindings menu is listening.
+#[derive(Resource, Debug, Default)]
+pub struct Rebinding(Option<InputAction>);
+
+#[derive(Component)]
+pub struct BindingsPanel;
+
+#[derive(Component)]
+pub struct BindingButton(InputAction);
+
+#[derive(Component)]
+pub struct BindingsTitle;
+
+fn make_bindings_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(30.0),
+                    top: Val::Percent(10.0),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                // Above the menus, so it can be opened from the settings menu
+                z_index: ZIndex::Global(2),
+                ..default()
+            },
+            BindingsPanel,
+            // Tracked so clicks on the panel don't select the tiles behind it
+            Interaction::default(),
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section("", TextStyle::default()),
+                BindingsTitle,
+            ));
+            InputAction::ALL.iter().for_each(|action| {
+                panel
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(4.0)),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        BindingButton(*action),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section("", TextStyle::default()));
+                    });
+            });
+        });
+}
+
+fn toggle_bindings_ui(
+    inputs: ActionInput,
+    mut rebinding: ResMut<Rebinding>,
+    mut q_panel: Query<&mut Visibility, With<BindingsPanel>>,
+) {
+    if !inputs.just_pressed(InputAction::ToggleBindings) {
+        return;
+    }
+    rebinding.0 = None;
+    q_panel.iter_mut().for_each(|mut visibility| {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    })
+}
+
+fn start_rebind(
+    q_buttons: Query<(&Interaction, &BindingButton), Changed<Interaction>>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(_, BindingButton(action))| rebinding.0 = Some(*action));
+}
+
+/// Runs before gameplay systems and swallows the key, so rebinding doesn't also trigger it.
+/// Actions that can't share the new key swap over to the rebound action's old keys.
+fn capture_rebind(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut input_map: ResMut<InputMap>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = keys.get_just_pressed().next().copied() else {
+        return;
+    };
+    keys.clear_just_pressed(key);
+    rebinding.0 = None;
+    if key == KeyCode::Escape {
+        return;
+    }
+    let old = input_map.keys(action).to_vec();
+    InputAction::ALL
+        .into_iter()
+        .filter(|other| *other != action && !action.can_share(*other))
+        .for_each(|other| {
+            let Some(keys) = input_map.bindings.get_mut(&other) else {
+                return;
+            };
+            if !keys.contains(&key) {
+                return;
+            }
+            keys.retain(|bound| *bound != key);
+            old.iter().for_each(|old_key| {
+                if !keys.contains(old_key) {
+                    keys.push(*old_key);
+                }
+            });
+            notify_queue.push(Notification::new(
+                Category::Settings,
+                locale.fmt(
+                    "bindings-swapped",
+                    &[
+                        ("action", &locale.t(action.key())),
+                        ("other", &locale.t(other.key())),
+                    ],
+                ),
+            ));
+        });
+    input_map.bindings.insert(action, vec![key]);
+    input_map.save();
+}
+
+fn update_bindings_ui(
+    input_map: Res<InputMap>,
+    rebinding: Res<Rebinding>,
+    locale: Res<Locale>,
+    mut q_title: Query<&mut Text, With<BindingsTitle>>,
+    mut q_buttons: Query<(&BindingButton, &Children, &mut BackgroundColor)>,
+    mut q_text: Query<&mut Text, Without<BindingsTitle>>,
+) {
+    if !input_map.is_changed() && !rebinding.is_changed() && !locale.is_changed() {
+        return;
+    }
+    q_title.iter_mut().for_each(|mut text| {
+        *text = Text::from_section(locale.t("bindings-title"), default());
+    });
+    let conflicts = input_map.conflicts();
+    q_buttons
+        .iter_mut()
+        .for_each(|(BindingButton(action), children, mut background)| {
+            let conflicting = conflicts.iter().any(|(a, b)| a == action || b == action);
+            *background = match conflicting {
+                true => Color::srgb(0.5, 0.1, 0.1),
+                false => Color::srgb(0.2, 0.2, 0.2),
+            }
+            .into();
+
+            let mut key = match rebinding.0 {
+                Some(rebind) if rebind == *action => locale.t("bindings-press-key"),
+                _ => match input_map.keys(*action) {
+                    [] => locale.t("key-unbound"),
+                    keys => keys
+                        .iter()
+                        .map(|key| format!("{:?}", key))
+                        .collect::<Vec<_>>()
+                        .join(" / "),
+                },
+            };
+            if let Some(button) = input_map.button(*action) {
+                key += &format!(" / {:?}", button);
+            }
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                text.sections[0].value = locale.fmt(
+                    "bindings-entry",
+                    &[("action", &locale.t(action.key())), ("key", &key)],
+                );
+            }
+        })
+}
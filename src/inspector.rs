@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::{
+    constants::TOWER_TS,
+    currency::Dormant,
+    game::GameState,
+    locale::Locale,
+    research::Research,
+    select_tile::{Invested, Selected, TileType},
+    world::Position,
+};
+
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, make_inspector).add_systems(
+            Update,
+            update_inspector.run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Inspector;
+
+fn make_inspector(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle::default())
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(8.0),
+                top: Val::Px(200.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        Inspector,
+        Interaction::default(),
+    ));
+}
+
+/// Details of a single tile.
+fn describe_tile(
+    tile_type: &TileType,
+    position: &Position,
+    invested: &Invested,
+    dormant: bool,
+    research: &Research,
+    locale: &Locale,
+) -> Vec<String> {
+    let mut lines = vec![
+        locale.fmt(
+            "inspector-position",
+            &[
+                ("x", &locale.number(position.0.x)),
+                ("z", &locale.number(position.0.z)),
+            ],
+        ),
+        tile_type.localized(locale),
+    ];
+    if tile_type.is_floor() {
+        lines.push(locale.t("inspector-floor"));
+    }
+    if tile_type.is_farm() {
+        lines.push(locale.fmt(
+            "inspector-level",
+            &[
+                ("level", &locale.number(tile_type.get_level())),
+                ("cap", &locale.number(research.farm_level_cap())),
+            ],
+        ));
+        if let Some((resource, amount)) = tile_type.production() {
+            lines.push(locale.fmt(
+                "tile-production",
+                &[
+                    ("amount", &locale.number(research.farm_yield(amount))),
+                    ("resource", &locale.t(resource.key())),
+                    ("period", &locale.number(TOWER_TS)),
+                ],
+            ));
+        }
+    }
+    if tile_type.is_tile() {
+        lines.push(locale.fmt(
+            "inspector-level",
+            &[
+                ("level", &locale.number(tile_type.get_level())),
+                ("cap", &locale.number(research.tower_level_cap())),
+            ],
+        ));
+        if let Some(range) = tile_type.range() {
+            lines.push(locale.fmt("inspector-range", &[("range", &locale.number(range))]));
+        }
+        lines.push(locale.fmt(
+            "tile-upkeep",
+            &[
+                ("amount", &locale.number(tile_type.upkeep())),
+                ("period", &locale.number(TOWER_TS)),
+            ],
+        ));
+        lines.push(match dormant {
+            true => locale.t("inspector-dormant"),
+            false => locale.t("inspector-active"),
+        });
+    }
+    if tile_type.is_farm() || tile_type.is_tile() {
+        lines.push(locale.fmt(
+            "inspector-invested",
+            &[("amount", &locale.number(invested.0))],
+        ));
+        lines.push(locale.fmt(
+            "inspector-sell-value",
+            &[("amount", &locale.number(invested.sell_value()))],
+        ));
+    }
+    lines
+}
+
+fn update_inspector(
+    research: Res<Research>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&Selected, &TileType, &Position, &Invested, Has<Dormant>)>,
+    mut q_inspector: Query<(&mut Text, &mut Visibility), With<Inspector>>,
+) {
+    let Ok((mut text, mut visibility)) = q_inspector.get_single_mut() else {
+        return;
+    };
+    let selected = q_tiles.iter().filter(|(sel, ..)| sel.0).collect::<Vec<_>>();
+    let lines = match selected.as_slice() {
+        [] => {
+            *visibility = Visibility::Hidden;
+            return;
+        }
+        [(_, tile_type, position, invested, dormant)] => {
+            describe_tile(tile_type, position, invested, *dormant, &research, &locale)
+        }
+        many => vec![
+            locale.fmt("selection-count", &[("count", &locale.number(many.len()))]),
+            locale.fmt(
+                "inspector-invested",
+                &[(
+                    "amount",
+                    &locale.number(
+                        many.iter()
+                            .map(|(_, _, _, invested, _)| invested.0)
+                            .sum::<usize>(),
+                    ),
+                )],
+            ),
+            locale.fmt(
+                "inspector-sell-value",
+                &[(
+                    "amount",
+                    &locale.number(
+                        many.iter()
+                            .map(|(_, _, _, invested, _)| invested.sell_value())
+                            .sum::<usize>(),
+                    ),
+                )],
+            ),
+        ],
+    };
+    *visibility = Visibility::Inherited;
+    let value = lines.join("\n");
+    // Only touch the text when it changes, to avoid relayout every frame
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Display;
+
+use bevy::{prelude::*, utils::HashMap, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Locale::new(
+            [
+                (Language::English, include_str!("../assets/locales/en.ron")),
+                (Language::German, include_str!("../assets/locales/de.ron")),
+            ]
+            .into_iter()
+            .map(|(language, catalog)| {
+                (
+                    language,
+                    ron::from_str(catalog).expect("Invalid message catalog!"),
+                )
+            })
+            .collect(),
+        ))
+        .add_systems(
+            PreUpdate,
+            switch_language.run_if(resource_changed::<Settings>),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// The language's own name for itself, so it can be found in any language.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    /// Thousands and decimal separators.
+    fn separators(&self) -> (char, char) {
+        match self {
+            Language::English => (',', '.'),
+            Language::German => ('.', ','),
+        }
+    }
+}
+
+/// Message catalogs for every language, and which one is in use.
+///
+/// Messages may contain `{name}` placeholders, filled in by [`Locale::fmt`]. Keys missing from
+/// a catalog fall back to English, then to the key itself.
+#[derive(Resource, Debug)]
+pub struct Locale {
+    pub language: Language,
+    catalogs: HashMap<Language, HashMap<String, String>>,
+}
+
+impl Locale {
+    fn new(catalogs: HashMap<Language, HashMap<String, String>>) -> Self {
+        Self {
+            language: Language::default(),
+            catalogs,
+        }
+    }
+
+    fn lookup(&self, language: Language, key: &str) -> Option<&String> {
+        self.catalogs.get(&language)?.get(key)
+    }
+
+    /// The message for `key` in the current language.
+    pub fn t(&self, key: &str) -> String {
+        self.lookup(self.language, key)
+            .or_else(|| self.lookup(Language::English, key))
+            .cloned()
+            .unwrap_or_else(|| {
+                warn!("Missing message {:?}", key);
+                key.to_owned()
+            })
+    }
+
+    /// The message for `key` with its placeholders filled in. The message is read once from the
+    /// start, so braces inside a value, like in a name the player typed, are left as they are.
+    pub fn fmt(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let message = self.t(key);
+        let mut out = String::with_capacity(message.len());
+        let mut rest = message.as_str();
+        while let Some(open) = rest.find('{') {
+            out += &rest[..open];
+            rest = &rest[open + 1..];
+            let value = rest.find('}').and_then(|close| {
+                let (_, value) = args.iter().find(|(name, _)| *name == &rest[..close])?;
+                Some((close, value))
+            });
+            match value {
+                Some((close, value)) => {
+                    out += &value.to_string();
+                    rest = &rest[close + 1..];
+                }
+                None => out.push('{'),
+            }
+        }
+        out + rest
+    }
+
+    /// `value` with the current language's digit grouping and decimal separator.
+    pub fn number(&self, value: impl Display) -> String {
+        let raw = value.to_string();
+        let digits = raw.trim_start_matches(['-', '+']);
+        let sign = &raw[..raw.len() - digits.len()];
+        let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+        let (group, decimal) = self.language.separators();
+        let mut out = sign.to_owned();
+        int.chars().enumerate().for_each(|(i, digit)| {
+            if i > 0 && (int.len() - i) % 3 == 0 {
+                out.push(group);
+            }
+            out.push(digit);
+        });
+        if !frac.is_empty() {
+            out.push(decimal);
+            out += frac;
+        }
+        out
+    }
+
+    /// Like [`Locale::number`], but always with a sign.
+    pub fn signed(&self, value: isize) -> String {
+        self.number(format!("{:+}", value))
+    }
+}
+
+fn switch_language(
+    settings: Res<Settings>,
+    mut locale: ResMut<Locale>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if locale.language != settings.language {
+        locale.language = settings.language;
+    }
+    if let Ok(mut window) = q_window.get_single_mut() {
+        window.title = locale.t("window-title");
+    }
+}
@@ -1,13 +1,28 @@
 use bevy::prelude::*;
 use game::GamePlugin;
 
+mod blueprint;
+mod build_queue;
 mod camera;
 mod constants;
+mod context_menu;
 mod currency;
 mod game;
+mod history;
+mod input_map;
+mod inspector;
+mod locale;
+mod market;
+mod menu;
+mod minimap;
+mod preview;
+mod research;
 mod select_tile;
+mod settings;
+mod stats;
 mod ui;
 mod util;
+mod wave;
 mod world;
 
 fn main() {
@@ -0,0 +1,269 @@
+use bevy::{prelude::*, utils::HashMap};
+use itertools::iproduct;
+
+use crate::{
+    constants::{MARKET_IMPACT, MARKET_LOT, MARKET_MIN_PRICE, MARKET_RECOVERY, MARKET_SETTLED},
+    currency::{Currency, Ledger, LedgerCategory},
+    game::GameState,
+    locale::Locale,
+    select_tile::ResourceType,
+    ui::{make_ui, SideColumn},
+    util::{Category, Notification, NotifyQueue},
+};
+
+pub struct MarketPlugin;
+
+impl Plugin for MarketPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Market>()
+            .add_systems(Startup, make_market_ui.after(make_ui))
+            .add_systems(
+                Update,
+                (trade, recover_prices, update_market_ui).run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Price of each resource in a common unit. Exchange rates are the ratio of two prices.
+#[derive(Resource, Debug)]
+pub struct Market {
+    prices: HashMap<ResourceType, f32>,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        Self {
+            prices: ResourceType::ALL.iter().map(|r| (*r, 1.0)).collect(),
+        }
+    }
+}
+
+impl Market {
+    pub fn price(&self, resource: ResourceType) -> f32 {
+        self.prices.get(&resource).copied().unwrap_or(1.0)
+    }
+
+    /// Amount of `to` received for `amount` of `from` at the current rate.
+    pub fn quote(&self, from: ResourceType, to: ResourceType, amount: usize) -> usize {
+        (amount as f32 * self.price(from) / self.price(to)) as usize
+    }
+
+    /// Selling pushes the price of `from` down and buying pushes the price of `to` up.
+    fn exchange(&mut self, from: ResourceType, to: ResourceType) {
+        self.prices
+            .entry(from)
+            .and_modify(|p| *p = (*p * (1.0 - MARKET_IMPACT)).max(MARKET_MIN_PRICE));
+        self.prices
+            .entry(to)
+            .and_modify(|p| *p *= 1.0 + MARKET_IMPACT);
+    }
+}
+
+#[derive(Component)]
+pub struct MarketButton {
+    from: ResourceType,
+    to: ResourceType,
+}
+
+#[derive(Component)]
+pub struct MarketPrice(ResourceType);
+
+#[derive(Component)]
+pub struct MarketTitle;
+
+/// Stacked below the wave HUD, so the two don't overlap.
+fn make_market_ui(mut commands: Commands, q_column: Query<Entity, With<SideColumn>>) {
+    let market = commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+            // Tracked so clicks on the panel don't select the tiles behind it
+            Interaction::default(),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section("", TextStyle::default()),
+                MarketTitle,
+            ));
+            ResourceType::ALL.iter().for_each(|resource| {
+                parent.spawn((
+                    TextBundle::from_section("", TextStyle::default()),
+                    MarketPrice(*resource),
+                ));
+            });
+            iproduct!(ResourceType::ALL, ResourceType::ALL)
+                .filter(|(from, to)| from != to)
+                .for_each(|(from, to)| {
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: Style {
+                                    padding: UiRect::all(Val::Px(4.0)),
+                                    ..default()
+                                },
+                                background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                                ..default()
+                            },
+                            MarketButton { from, to },
+                        ))
+                        .with_children(|button| {
+                            button.spawn(TextBundle::from_section("", TextStyle::default()));
+                        });
+                });
+        })
+        .id();
+    if let Ok(column) = q_column.get_single() {
+        commands.entity(column).add_child(market);
+    }
+}
+
+fn trade(
+    q_buttons: Query<(&Interaction, &MarketButton), Changed<Interaction>>,
+    mut market: ResMut<Market>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(_, MarketButton { from, to })| {
+            if currency.get(*from) < MARKET_LOT {
+                notify_queue.push(
+                    Notification::new(
+                        Category::Economy,
+                        locale.fmt("market-short", &[("resource", &locale.t(from.key()))]),
+                    )
+                    .warning(),
+                );
+                return;
+            }
+            let received = market.quote(*from, *to, MARKET_LOT);
+            if received == 0 {
+                notify_queue.push(
+                    Notification::new(
+                        Category::Economy,
+                        locale.fmt(
+                            "market-worthless",
+                            &[
+                                ("amount", &locale.number(MARKET_LOT)),
+                                ("from", &locale.t(from.key())),
+                                ("to", &locale.t(to.key())),
+                            ],
+                        ),
+                    )
+                    .warning(),
+                );
+                return;
+            }
+            *currency.get_mut(*from) -= MARKET_LOT;
+            *currency.get_mut(*to) += received;
+            ledger.record(LedgerCategory::Market, *from, -(MARKET_LOT as isize));
+            ledger.record(LedgerCategory::Market, *to, received as isize);
+            market.exchange(*from, *to);
+            notify_queue.push(Notification::new(
+                Category::Economy,
+                locale.fmt(
+                    "market-sold",
+                    &[
+                        ("amount", &locale.number(MARKET_LOT)),
+                        ("from", &locale.t(from.key())),
+                        ("received", &locale.number(received)),
+                        ("to", &locale.t(to.key())),
+                    ],
+                ),
+            ));
+        })
+}
+
+/// Prices drift back towards their baseline while nobody is trading, and settle on it once
+/// close enough. The market only counts as changed while a price is still moving.
+fn recover_prices(mut market: ResMut<Market>, time: Res<Time>) {
+    let recovery = (MARKET_RECOVERY * time.delta_seconds()).min(1.0);
+    let mut moved = false;
+    market
+        .bypass_change_detection()
+        .prices
+        .values_mut()
+        .for_each(|price| {
+            let next = match (1.0 - *price).abs() < MARKET_SETTLED {
+                true => 1.0,
+                false => *price + (1.0 - *price) * recovery,
+            };
+            if *price != next {
+                *price = next;
+                moved = true;
+            }
+        });
+    if moved {
+        market.set_changed();
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_market_ui(
+    market: Res<Market>,
+    locale: Res<Locale>,
+    mut q_title: Query<&mut Text, (With<MarketTitle>, Without<MarketPrice>)>,
+    mut q_prices: Query<(&MarketPrice, &mut Text), Without<MarketTitle>>,
+    mut q_buttons: Query<(&MarketButton, &Children, &mut BackgroundColor)>,
+    mut q_text: Query<&mut Text, (Without<MarketPrice>, Without<MarketTitle>)>,
+) {
+    if !market.is_changed() && !locale.is_changed() {
+        return;
+    }
+    q_title.iter_mut().for_each(|mut text| {
+        *text = Text::from_section(locale.t("market-title"), default());
+    });
+    q_prices
+        .iter_mut()
+        .for_each(|(MarketPrice(resource), mut text)| {
+            *text = Text::from_section(
+                locale.fmt(
+                    "market-price",
+                    &[
+                        ("resource", &locale.t(resource.key())),
+                        (
+                            "price",
+                            &locale.number(format!("{:.2}", market.price(*resource))),
+                        ),
+                    ],
+                ),
+                default(),
+            );
+        });
+    q_buttons
+        .iter_mut()
+        .for_each(|(MarketButton { from, to }, children, mut background)| {
+            let received = market.quote(*from, *to, MARKET_LOT);
+            // Trades that would buy nothing are refused, so the button looks disabled
+            background.0 = match received {
+                0 => Color::srgb(0.1, 0.1, 0.1),
+                _ => Color::srgb(0.2, 0.2, 0.2),
+            };
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                *text = Text::from_section(
+                    locale.fmt(
+                        "market-sell",
+                        &[
+                            ("amount", &locale.number(MARKET_LOT)),
+                            ("from", &locale.t(from.key())),
+                            ("received", &locale.number(received)),
+                            ("to", &locale.t(to.key())),
+                        ],
+                    ),
+                    default(),
+                );
+            }
+        });
+}
@@ -0,0 +1,474 @@
+use bevy::{app::AppExit, prelude::*, ui::FocusPolicy};
+
+use crate::{
+    blueprint::{NamingBlueprint, Pasting},
+    build_queue::BuildQueue,
+    context_menu::RadialMenu,
+    currency::{Currency, Ledger},
+    game::GameState,
+    history::History,
+    input_map::{ActionInput, BindingsPanel, InputAction},
+    locale::Locale,
+    market::Market,
+    preview::Ghost,
+    research::Research,
+    select_tile::{BoxSelect, PendingAction, SelectionCursor},
+    settings::Settings,
+    stats::SessionStats,
+    ui::FloatingText,
+    util::Toast,
+    wave::WaveState,
+    world::Tile,
+};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.enable_state_scoped_entities::<GameState>()
+            .init_resource::<SettingsReturn>()
+            .init_resource::<MenuFocus>()
+            .add_event::<MenuButtonPressed>()
+            .add_systems(OnEnter(GameState::MainMenu), make_main_menu)
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (reset_game, make_loading_screen),
+            )
+            .add_systems(OnEnter(GameState::Paused), make_pause_menu)
+            .add_systems(OnEnter(GameState::Settings), make_settings_menu)
+            .add_systems(OnExit(GameState::Settings), hide_bindings)
+            .add_systems(
+                Update,
+                (
+                    (
+                        navigate_menu,
+                        pick_menu_button,
+                        highlight_menu_buttons,
+                        press_menu_button,
+                        update_menu_labels,
+                    )
+                        .chain(),
+                    finish_loading.run_if(in_state(GameState::Loading)),
+                    toggle_pause
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused))),
+                ),
+            );
+    }
+}
+
+/// State to go back to when leaving the settings menu.
+#[derive(Resource, Debug)]
+pub struct SettingsReturn(GameState);
+
+impl Default for SettingsReturn {
+    fn default() -> Self {
+        Self(GameState::MainMenu)
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuButton {
+    NewGame,
+    Continue,
+    Resume,
+    Settings,
+    Bindings,
+    Fullscreen,
+    Resolution,
+    UiScale,
+    Language,
+    Back,
+    MainMenu,
+    EndGame,
+    SaveReport,
+    Quit,
+}
+
+impl MenuButton {
+    fn label(&self, settings: &Settings, locale: &Locale) -> String {
+        match self {
+            MenuButton::NewGame => locale.t("menu-new-game"),
+            MenuButton::Continue => locale.t("menu-continue"),
+            MenuButton::Resume => locale.t("menu-resume"),
+            MenuButton::Settings => locale.t("menu-settings"),
+            MenuButton::Bindings => locale.t("menu-key-bindings"),
+            MenuButton::Fullscreen => match settings.fullscreen {
+                true => locale.t("menu-fullscreen-on"),
+                false => locale.t("menu-fullscreen-off"),
+            },
+            MenuButton::Resolution => {
+                let (width, height) = settings.resolution;
+                locale.fmt("menu-resolution", &[("width", &width), ("height", &height)])
+            }
+            MenuButton::UiScale => locale.fmt(
+                "menu-ui-scale",
+                &[(
+                    "percent",
+                    &locale.number((settings.ui_scale * 100.0).round()),
+                )],
+            ),
+            MenuButton::Language => {
+                locale.fmt("menu-language", &[("language", &settings.language.name())])
+            }
+            MenuButton::Back => locale.t("menu-back"),
+            MenuButton::MainMenu => locale.t("menu-main-menu"),
+            MenuButton::EndGame => locale.t("menu-end-game"),
+            MenuButton::SaveReport => locale.t("menu-save-report"),
+            MenuButton::Quit => locale.t("menu-quit"),
+        }
+    }
+}
+
+/// A menu button picked with the mouse, or with Confirm while it has the focus.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct MenuButtonPressed(pub MenuButton);
+
+/// Menu button that keyboard and gamepad navigation has moved to.
+#[derive(Resource, Debug, Default)]
+pub struct MenuFocus(Option<Entity>);
+
+/// Root of a full screen menu.
+#[derive(Component)]
+pub struct Menu;
+
+/// Menu heading, holding its message catalog key.
+#[derive(Component)]
+pub struct MenuTitle(&'static str);
+
+/// Full screen menu with a title and a column of buttons, removed when `state` is left.
+pub(crate) fn spawn_menu(
+    commands: &mut Commands,
+    state: GameState,
+    title: &'static str,
+    buttons: &[MenuButton],
+) -> Entity {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.05, 0.05, 0.08, 0.92).into(),
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(1),
+                ..default()
+            },
+            Menu,
+            StateScoped(state),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 48.0,
+                        ..default()
+                    },
+                ),
+                MenuTitle(title),
+            ));
+            buttons.iter().for_each(|button| {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(200.0),
+                                padding: UiRect::all(Val::Px(8.0)),
+                                justify_content: JustifyContent::Center,
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                            ..default()
+                        },
+                        *button,
+                    ))
+                    .with_children(|button_parent| {
+                        // Filled in by update_menu_labels
+                        button_parent.spawn(TextBundle::from_section("", TextStyle::default()));
+                    });
+            });
+        })
+        .id()
+}
+
+fn make_main_menu(mut commands: Commands, q_tiles: Query<(), With<Tile>>) {
+    // Continue only makes sense once a world has been spawned
+    let buttons = match q_tiles.is_empty() {
+        true => vec![MenuButton::NewGame, MenuButton::Settings, MenuButton::Quit],
+        false => vec![
+            MenuButton::Continue,
+            MenuButton::NewGame,
+            MenuButton::Settings,
+            MenuButton::Quit,
+        ],
+    };
+    spawn_menu(&mut commands, GameState::MainMenu, "window-title", &buttons);
+}
+
+fn make_loading_screen(mut commands: Commands) {
+    spawn_menu(&mut commands, GameState::Loading, "menu-loading", &[]);
+}
+
+fn make_pause_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        GameState::Paused,
+        "menu-paused",
+        &[
+            MenuButton::Resume,
+            MenuButton::Settings,
+            MenuButton::MainMenu,
+            MenuButton::EndGame,
+            MenuButton::Quit,
+        ],
+    );
+}
+
+fn make_settings_menu(mut commands: Commands) {
+    spawn_menu(
+        &mut commands,
+        GameState::Settings,
+        "menu-settings",
+        &[
+            MenuButton::Fullscreen,
+            MenuButton::Resolution,
+            MenuButton::UiScale,
+            MenuButton::Language,
+            MenuButton::Bindings,
+            MenuButton::Back,
+        ],
+    );
+}
+
+fn hide_bindings(mut q_panel: Query<&mut Visibility, With<BindingsPanel>>) {
+    q_panel
+        .iter_mut()
+        .for_each(|mut visibility| *visibility = Visibility::Hidden);
+}
+
+/// Moves the focus up or down through the open menu's buttons, wrapping around.
+fn navigate_menu(
+    inputs: ActionInput,
+    mut focus: ResMut<MenuFocus>,
+    q_menus: Query<&Children, With<Menu>>,
+    q_buttons: Query<(), With<MenuButton>>,
+) {
+    let step = match (
+        inputs.just_pressed(InputAction::NavUp),
+        inputs.just_pressed(InputAction::NavDown),
+    ) {
+        (true, false) => -1,
+        (false, true) => 1,
+        _ => return,
+    };
+    let buttons = q_menus
+        .iter()
+        .flat_map(|children| children.iter())
+        .filter(|child| q_buttons.contains(**child))
+        .copied()
+        .collect::<Vec<_>>();
+    if buttons.is_empty() {
+        return;
+    }
+    let len = buttons.len() as isize;
+    let next = match focus
+        .0
+        .and_then(|focused| buttons.iter().position(|button| *button == focused))
+    {
+        Some(index) => (index as isize + step).rem_euclid(len),
+        None if step > 0 => 0,
+        None => len - 1,
+    };
+    focus.0 = Some(buttons[next as usize]);
+}
+
+fn pick_menu_button(
+    inputs: ActionInput,
+    focus: Res<MenuFocus>,
+    q_clicked: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    q_buttons: Query<&MenuButton>,
+    mut pressed: EventWriter<MenuButtonPressed>,
+) {
+    q_clicked
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(_, button)| {
+            pressed.send(MenuButtonPressed(*button));
+        });
+    if !inputs.just_pressed(InputAction::Confirm) {
+        return;
+    }
+    if let Some(button) = focus.0.and_then(|focused| q_buttons.get(focused).ok()) {
+        pressed.send(MenuButtonPressed(*button));
+    }
+}
+
+/// Shades buttons by mouse interaction, showing the focused one as if hovered.
+fn highlight_menu_buttons(
+    focus: Res<MenuFocus>,
+    mut q_buttons: Query<(Entity, &Interaction, &mut BackgroundColor), With<MenuButton>>,
+) {
+    q_buttons
+        .iter_mut()
+        .for_each(|(entity, interaction, mut background)| {
+            let fill = match interaction {
+                Interaction::Pressed => Color::srgb(0.4, 0.4, 0.4),
+                Interaction::Hovered => Color::srgb(0.3, 0.3, 0.3),
+                Interaction::None if focus.0 == Some(entity) => Color::srgb(0.3, 0.3, 0.3),
+                Interaction::None => Color::srgb(0.2, 0.2, 0.2),
+            };
+            if background.0 != fill {
+                background.0 = fill;
+            }
+        })
+}
+
+fn press_menu_button(
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut settings_return: ResMut<SettingsReturn>,
+    mut settings: ResMut<Settings>,
+    mut exit: EventWriter<AppExit>,
+    mut pressed: EventReader<MenuButtonPressed>,
+    mut q_panel: Query<&mut Visibility, With<BindingsPanel>>,
+) {
+    pressed
+        .read()
+        .for_each(|MenuButtonPressed(button)| match button {
+            MenuButton::NewGame => next_state.set(GameState::Loading),
+            MenuButton::Continue | MenuButton::Resume => next_state.set(GameState::Playing),
+            MenuButton::Settings => {
+                settings_return.0 = *state.get();
+                next_state.set(GameState::Settings);
+            }
+            MenuButton::Bindings => q_panel.iter_mut().for_each(|mut visibility| {
+                *visibility = match *visibility {
+                    Visibility::Hidden => Visibility::Inherited,
+                    _ => Visibility::Hidden,
+                };
+            }),
+            MenuButton::Fullscreen => {
+                settings.fullscreen = !settings.fullscreen;
+                settings.save();
+            }
+            MenuButton::Resolution => {
+                settings.cycle_resolution();
+                settings.save();
+            }
+            MenuButton::UiScale => {
+                settings.cycle_ui_scale();
+                settings.save();
+            }
+            MenuButton::Language => {
+                settings.cycle_language();
+                settings.save();
+            }
+            MenuButton::Back => next_state.set(settings_return.0),
+            MenuButton::MainMenu => next_state.set(GameState::MainMenu),
+            MenuButton::EndGame => next_state.set(GameState::Summary),
+            // Handled by the stats plugin, which owns the report
+            MenuButton::SaveReport => {}
+            MenuButton::Quit => {
+                exit.send(AppExit::Success);
+            }
+        })
+}
+
+fn update_menu_labels(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    q_buttons: Query<(Ref<MenuButton>, &Children)>,
+    mut q_titles: Query<(Ref<MenuTitle>, &mut Text)>,
+    mut q_text: Query<&mut Text, Without<MenuTitle>>,
+) {
+    let refresh = settings.is_changed() || locale.is_changed();
+    q_titles
+        .iter_mut()
+        .filter(|(title, _)| title.is_added() || refresh)
+        .for_each(|(title, mut text)| text.sections[0].value = locale.t(title.0));
+    q_buttons
+        .iter()
+        .filter(|(button, _)| button.is_added() || refresh)
+        .for_each(|(button, children)| {
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                text.sections[0].value = button.label(&settings, &locale);
+            }
+        })
+}
+
+/// Starts a new game from scratch. The world itself is respawned by [`crate::world`].
+#[allow(clippy::type_complexity)]
+fn reset_game(
+    mut commands: Commands,
+    mut research: ResMut<Research>,
+    q_leftovers: Query<
+        Entity,
+        Or<(
+            With<Ghost>,
+            With<RadialMenu>,
+            With<FloatingText>,
+            With<Toast>,
+        )>,
+    >,
+) {
+    // Ghosts, open menus and messages left over from the last game
+    q_leftovers
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn_recursive());
+    commands.insert_resource(Currency::default());
+    commands.insert_resource(Ledger::default());
+    commands.insert_resource(WaveState::default());
+    commands.insert_resource(Market::default());
+    commands.insert_resource(History::default());
+    commands.insert_resource(BuildQueue::default());
+    commands.insert_resource(Pasting::default());
+    commands.insert_resource(NamingBlueprint::default());
+    commands.insert_resource(PendingAction::default());
+    commands.insert_resource(SelectionCursor::default());
+    commands.insert_resource(BoxSelect::default());
+    commands.insert_resource(SessionStats::default());
+    research.reset();
+}
+
+/// Starts playing once every tile model has loaded.
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    q_scenes: Query<&Handle<Scene>, With<Tile>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !q_scenes.is_empty()
+        && q_scenes
+            .iter()
+            .all(|scene| asset_server.is_loaded_with_dependencies(scene))
+    {
+        next_state.set(GameState::Playing);
+    }
+}
+
+fn toggle_pause(
+    inputs: ActionInput,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    q_panel: Query<&Visibility, With<BindingsPanel>>,
+) {
+    // Escape also cancels rebinding, so leave it to the bindings panel while that is open
+    if !inputs.just_pressed(InputAction::Pause)
+        || q_panel
+            .iter()
+            .any(|visibility| *visibility != Visibility::Hidden)
+    {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
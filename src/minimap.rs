@@ -0,0 +1,248 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition, window::PrimaryWindow};
+
+use crate::{
+    camera::{GameCamera, PanOrbitState},
+    constants::{MINIMAP_CELL, TILE_SIZE, WORLD_SIZE},
+    game::GameState,
+    select_tile::{ResourceType, TileType},
+    world::Position,
+};
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, make_minimap).add_systems(
+            Update,
+            (
+                despawn_minimap_cells,
+                (
+                    spawn_minimap_cells,
+                    update_minimap_cells,
+                    update_minimap_view,
+                    click_minimap,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            ),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Minimap;
+
+/// Minimap square showing one tile.
+#[derive(Component)]
+pub struct MinimapCell(Entity);
+
+/// Outline of the ground area the camera sees.
+#[derive(Component)]
+pub struct MinimapView;
+
+/// Dot at the point the camera orbits around.
+#[derive(Component)]
+pub struct MinimapCenter;
+
+const MINIMAP_SIZE: f32 = MINIMAP_CELL * WORLD_SIZE as f32;
+
+/// Minimap pixel for a world position. Tile `(i, j)` is centered on `(i, j) * 2 * TILE_SIZE`
+/// less one `TILE_SIZE`, see [`crate::world::tile_center`].
+fn world_to_minimap(world: Vec3) -> Vec2 {
+    let grid = (Vec2::new(world.x, world.z) + TILE_SIZE) / (2.0 * TILE_SIZE);
+    (grid + 0.5) * MINIMAP_CELL
+}
+
+fn minimap_to_world(pixel: Vec2, height: f32) -> Vec3 {
+    let ground = (pixel / MINIMAP_CELL - 0.5) * 2.0 * TILE_SIZE - TILE_SIZE;
+    Vec3::new(ground.x, height, ground.y)
+}
+
+fn cell_color(tile_type: &TileType) -> Color {
+    match tile_type {
+        TileType::Floor => Color::srgb(0.25, 0.25, 0.25),
+        TileType::Resource(ResourceType::Wood) => Color::srgb(0.1, 0.35, 0.1),
+        TileType::Resource(ResourceType::Stone) => Color::srgb(0.4, 0.4, 0.45),
+        TileType::Farm(_, ResourceType::Wood) => Color::srgb(0.35, 0.75, 0.25),
+        TileType::Farm(_, ResourceType::Stone) => Color::srgb(0.7, 0.7, 0.75),
+        TileType::Tile(_) => Color::srgb(0.85, 0.35, 0.15),
+    }
+}
+
+fn make_minimap(mut commands: Commands) {
+    commands
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(8.0),
+                    bottom: Val::Px(8.0),
+                    width: Val::Px(MINIMAP_SIZE),
+                    height: Val::Px(MINIMAP_SIZE),
+                    overflow: Overflow::clip(),
+                    ..default()
+                },
+                background_color: Color::BLACK.into(),
+                ..default()
+            },
+            RelativeCursorPosition::default(),
+            Minimap,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        border: UiRect::all(Val::Px(1.0)),
+                        ..default()
+                    },
+                    border_color: Color::WHITE.into(),
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                },
+                MinimapView,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Px(4.0),
+                        height: Val::Px(4.0),
+                        ..default()
+                    },
+                    background_color: Color::WHITE.into(),
+                    z_index: ZIndex::Local(1),
+                    ..default()
+                },
+                MinimapCenter,
+            ));
+        });
+}
+
+fn spawn_minimap_cells(
+    mut commands: Commands,
+    q_minimap: Query<Entity, With<Minimap>>,
+    q_tiles: Query<(Entity, &Position, &TileType), Added<Position>>,
+) {
+    let Ok(minimap) = q_minimap.get_single() else {
+        return;
+    };
+    q_tiles.iter().for_each(|(tile, position, tile_type)| {
+        let cell = commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Px(position.0.x * MINIMAP_CELL),
+                        top: Val::Px(position.0.z * MINIMAP_CELL),
+                        width: Val::Px(MINIMAP_CELL),
+                        height: Val::Px(MINIMAP_CELL),
+                        ..default()
+                    },
+                    background_color: cell_color(tile_type).into(),
+                    ..default()
+                },
+                MinimapCell(tile),
+            ))
+            .id();
+        commands.entity(minimap).add_child(cell);
+    })
+}
+
+/// Drops cells whose tile was despawned, when a new game replaces the world.
+fn despawn_minimap_cells(
+    mut commands: Commands,
+    mut removed: RemovedComponents<TileType>,
+    q_cells: Query<(Entity, &MinimapCell)>,
+) {
+    let removed = removed.read().collect::<Vec<_>>();
+    if removed.is_empty() {
+        return;
+    }
+    q_cells
+        .iter()
+        .filter(|(_, MinimapCell(tile))| removed.contains(tile))
+        .for_each(|(cell, _)| commands.entity(cell).despawn_recursive());
+}
+
+fn update_minimap_cells(
+    q_changed: Query<(), Changed<TileType>>,
+    q_tiles: Query<&TileType>,
+    mut q_cells: Query<(&MinimapCell, &mut BackgroundColor)>,
+) {
+    if q_changed.is_empty() {
+        return;
+    }
+    q_cells
+        .iter_mut()
+        .for_each(|(MinimapCell(tile), mut color)| {
+            if let Ok(tile_type) = q_tiles.get(*tile) {
+                *color = cell_color(tile_type).into();
+            }
+        })
+}
+
+/// Frames the ground points under the corners of the screen, and marks the orbit center.
+#[allow(clippy::type_complexity)]
+fn update_minimap_view(
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform, &PanOrbitState), With<GameCamera>>,
+    mut q_view: Query<&mut Style, (With<MinimapView>, Without<MinimapCenter>)>,
+    mut q_center: Query<&mut Style, (With<MinimapCenter>, Without<MinimapView>)>,
+) {
+    let (Ok(window), Ok((camera, camera_transform, state))) =
+        (q_window.get_single(), q_camera.get_single())
+    else {
+        return;
+    };
+
+    if let Ok(mut style) = q_center.get_single_mut() {
+        let center = world_to_minimap(state.center);
+        style.left = Val::Px(center.x - 2.0);
+        style.top = Val::Px(center.y - 2.0);
+    }
+
+    let Ok(mut style) = q_view.get_single_mut() else {
+        return;
+    };
+    let size = window.size();
+    let Some(rect) = [
+        Vec2::ZERO,
+        Vec2::new(size.x, 0.0),
+        size,
+        Vec2::new(0.0, size.y),
+    ]
+    .into_iter()
+    .filter_map(|corner| camera.viewport_to_world(camera_transform, corner))
+    .filter_map(|ray| {
+        let distance = ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y))?;
+        Some(world_to_minimap(ray.get_point(distance)))
+    })
+    .map(|corner| Rect::from_center_size(corner, Vec2::ZERO))
+    .reduce(|a, b| a.union(b)) else {
+        return;
+    };
+    let rect = rect.intersect(Rect::new(0.0, 0.0, MINIMAP_SIZE, MINIMAP_SIZE));
+    style.left = Val::Px(rect.min.x);
+    style.top = Val::Px(rect.min.y);
+    style.width = Val::Px(rect.width());
+    style.height = Val::Px(rect.height());
+}
+
+fn click_minimap(
+    q_minimap: Query<(&Interaction, &RelativeCursorPosition), With<Minimap>>,
+    mut q_camera: Query<(&mut PanOrbitState, &mut Transform), With<GameCamera>>,
+) {
+    let Some(cursor) = q_minimap
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .find_map(|(_, position)| position.normalized)
+    else {
+        return;
+    };
+    let Ok((mut state, mut transform)) = q_camera.get_single_mut() else {
+        return;
+    };
+    state.center = minimap_to_world(cursor * MINIMAP_SIZE, state.center.y);
+    // pan_orbit_camera only moves the camera on input, so place it here
+    transform.translation = state.center + transform.back() * state.radius;
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use crate::{
+    blueprint::BlueprintGhost,
+    constants::{GHOST_ALPHA, GHOST_LIFT, TOWER_TS},
+    currency::Currency,
+    game::GameState,
+    input_map::{InputAction, InputMap},
+    locale::Locale,
+    research::Research,
+    select_tile::{Action, Invested, PendingAction, Selected, TileType},
+};
+
+pub struct PreviewPlugin;
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, make_tooltip).add_systems(
+            Update,
+            (update_ghosts, make_ghosts_translucent, update_tooltip)
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// Translucent model of what a selected tile would become.
+#[derive(Component)]
+pub struct Ghost;
+
+#[derive(Component)]
+pub struct PreviewTooltip;
+
+fn make_tooltip(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle::default())
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        PreviewTooltip,
+    ));
+}
+
+#[allow(clippy::type_complexity)]
+fn update_ghosts(
+    mut commands: Commands,
+    pending: Res<PendingAction>,
+    research: Res<Research>,
+    asset_server: Res<AssetServer>,
+    q_changed: Query<(), Or<(Changed<Selected>, Changed<TileType>)>>,
+    q_tiles: Query<(&Selected, &TileType, &Transform)>,
+    q_ghosts: Query<Entity, (With<Ghost>, Without<BlueprintGhost>)>,
+) {
+    if !pending.is_changed() && q_changed.is_empty() {
+        return;
+    }
+    q_ghosts
+        .iter()
+        .for_each(|ghost| commands.entity(ghost).despawn_recursive());
+    let Some(action) = &pending.0 else {
+        return;
+    };
+    q_tiles
+        .iter()
+        .filter(|(sel, ..)| sel.0)
+        .filter_map(|(_, tile_type, transform)| {
+            Some((tile_type.available_action(action, &research)?, transform))
+        })
+        .for_each(|(next, transform)| {
+            commands.spawn((
+                SceneBundle {
+                    scene: asset_server.load(next.texture()),
+                    transform: Transform::from_translation(
+                        transform.translation + Vec3::Y * GHOST_LIFT,
+                    ),
+                    ..default()
+                },
+                Ghost,
+            ));
+        })
+}
+
+/// Ghost scenes share materials with the real tiles, so each ghost gets translucent copies.
+fn make_ghosts_translucent(
+    q_ghosts: Query<(), With<Ghost>>,
+    q_parents: Query<&Parent>,
+    mut q_materials: Query<
+        (Entity, &mut Handle<StandardMaterial>),
+        Added<Handle<StandardMaterial>>,
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    q_materials
+        .iter_mut()
+        .filter(|(entity, _)| {
+            q_parents
+                .iter_ancestors(*entity)
+                .any(|ancestor| q_ghosts.contains(ancestor))
+        })
+        .for_each(|(_, mut handle)| {
+            let Some(mut material) = materials.get(handle.id()).cloned() else {
+                return;
+            };
+            material.base_color.set_alpha(GHOST_ALPHA);
+            material.alpha_mode = AlphaMode::Blend;
+            *handle = materials.add(material);
+        })
+}
+
+fn update_tooltip(
+    pending: Res<PendingAction>,
+    research: Res<Research>,
+    currency: Res<Currency>,
+    input_map: Res<InputMap>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&Selected, &TileType, &Invested)>,
+    mut q_tooltip: Query<(&mut Text, &mut Visibility), With<PreviewTooltip>>,
+) {
+    let Ok((mut text, mut visibility)) = q_tooltip.get_single_mut() else {
+        return;
+    };
+    let Some(action) = &pending.0 else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    let previews = q_tiles
+        .iter()
+        .filter(|(sel, ..)| sel.0)
+        .filter_map(|(_, tile_type, invested)| {
+            let next = tile_type.available_action(action, &research)?;
+            let refund = match action {
+                Action::Sell => invested.sell_value(),
+                _ => 0,
+            };
+            Some((tile_type.get_action_cost(action), refund, next))
+        })
+        .collect::<Vec<_>>();
+
+    let name = locale.t(action.key());
+    let mut lines = match previews.as_slice() {
+        [] => vec![locale.fmt("preview-unavailable", &[("action", &name)])],
+        [(_, _, next)] => {
+            let mut lines = vec![locale.fmt(
+                "preview-result",
+                &[("action", &name), ("tile", &next.localized(&locale))],
+            )];
+            if let Some((resource, amount)) = next.production() {
+                lines.push(locale.fmt(
+                    "tile-production",
+                    &[
+                        ("amount", &locale.number(research.farm_yield(amount))),
+                        ("resource", &locale.t(resource.key())),
+                        ("period", &locale.number(TOWER_TS)),
+                    ],
+                ));
+            }
+            if next.upkeep() > 0 {
+                lines.push(locale.fmt(
+                    "tile-upkeep",
+                    &[
+                        ("amount", &locale.number(next.upkeep())),
+                        ("period", &locale.number(TOWER_TS)),
+                    ],
+                ));
+            }
+            lines
+        }
+        many => vec![locale.fmt(
+            "preview-many",
+            &[("action", &name), ("count", &locale.number(many.len()))],
+        )],
+    };
+
+    let cost = previews.iter().map(|(cost, ..)| cost).sum::<usize>();
+    let refund = previews.iter().map(|(_, refund, _)| refund).sum::<usize>();
+    if !previews.is_empty() {
+        lines.push(locale.fmt("preview-cost", &[("amount", &locale.number(cost))]));
+        if refund > 0 {
+            lines.push(locale.fmt("preview-refund", &[("amount", &locale.number(refund))]));
+        }
+        lines.push(match currency.wood >= cost {
+            true => input_map
+                .key(InputAction::Confirm)
+                .map_or(locale.t("preview-unbound"), |key| {
+                    locale.fmt("preview-confirm", &[("key", &format!("{:?}", key))])
+                }),
+            false => locale.fmt(
+                "preview-short",
+                &[("amount", &locale.number(cost - currency.wood))],
+            ),
+        });
+    }
+    text.sections[0].value = lines.join("\n");
+}
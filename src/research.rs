@@ -0,0 +1,438 @@
+use bevy::{prelude::*, utils::HashSet};
+use serde::Deserialize;
+
+use crate::{
+    constants::{BASE_FARM_LEVEL_CAP, BASE_TOWER_LEVEL_CAP},
+    currency::{Currency, Ledger, LedgerCategory},
+    game::GameState,
+    input_map::{ActionInput, InputAction},
+    locale::Locale,
+    select_tile::{Action, ResourceType, TileType},
+    util::{Category, Notification, NotifyQueue},
+};
+
+pub struct ResearchPlugin;
+
+impl Plugin for ResearchPlugin {
+    fn build(&self, app: &mut App) {
+        let techs =
+            ron::from_str(include_str!("../assets/research.ron")).expect("Invalid research tree!");
+        app.insert_resource(
+            Research::new(techs).unwrap_or_else(|err| panic!("Invalid research tree: {}", err)),
+        )
+        .add_systems(Startup, make_research_ui)
+        .add_systems(
+            Update,
+            (
+                progress_research,
+                start_research,
+                toggle_research_ui,
+                update_research_ui,
+            )
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Unlock {
+    Action(Action),
+    Farming(ResourceType),
+    TowerLevelCap(usize),
+    FarmLevelCap(usize),
+    FarmEfficiency(f32),
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Tech {
+    pub id: String,
+    pub cost: usize,
+    pub duration: f32,
+    #[serde(default)]
+    pub requires: Vec<String>,
+    pub unlocks: Vec<Unlock>,
+}
+
+impl Tech {
+    /// Message catalog key for the tech's name.
+    pub fn key(&self) -> String {
+        format!("tech-{}", self.id.replace('_', "-"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TechState {
+    Locked,
+    Available,
+    Researching,
+    Done,
+}
+
+/// The tech tree and the player's progress through it.
+///
+/// Anything that no tech unlocks is available from the start.
+#[derive(Resource, Debug)]
+pub struct Research {
+    techs: Vec<Tech>,
+    done: HashSet<String>,
+    active: Option<(String, Timer)>,
+}
+
+impl Research {
+    /// Fails if a tech requires one that doesn't exist, or requires itself, directly or through
+    /// its prerequisites.
+    fn new(techs: Vec<Tech>) -> Result<Self, String> {
+        let research = Self {
+            techs,
+            done: HashSet::new(),
+            active: None,
+        };
+        if let Some((tech, missing)) = research.techs.iter().find_map(|tech| {
+            tech.requires
+                .iter()
+                .find(|req| research.tech(req).is_none())
+                .map(|req| (&tech.id, req))
+        }) {
+            return Err(format!("{} requires unknown tech {}", tech, missing));
+        }
+        match research.find_cycle().map(str::to_owned) {
+            Some(id) => Err(format!("{} requires itself", id)),
+            None => Ok(research),
+        }
+    }
+
+    /// A tech whose prerequisites lead back to it.
+    fn find_cycle(&self) -> Option<&str> {
+        fn visit<'a>(research: &'a Research, id: &'a str, path: &mut Vec<&'a str>) -> bool {
+            if path.contains(&id) {
+                return true;
+            }
+            path.push(id);
+            let cyclic = research
+                .tech(id)
+                .is_some_and(|tech| tech.requires.iter().any(|req| visit(research, req, path)));
+            path.pop();
+            cyclic
+        }
+        self.techs
+            .iter()
+            .map(|tech| tech.id.as_str())
+            .find(|id| visit(self, id, &mut vec![]))
+    }
+
+    /// Forgets all progress, for a new game.
+    pub fn reset(&mut self) {
+        self.done.clear();
+        self.active = None;
+    }
+
+    fn tech(&self, id: &str) -> Option<&Tech> {
+        self.techs.iter().find(|tech| tech.id == id)
+    }
+
+    /// Number of prerequisites on the longest path to this tech. The tree is checked for cycles
+    /// when it is loaded, so this always ends.
+    fn depth(&self, id: &str) -> usize {
+        self.tech(id).map_or(0, |tech| {
+            tech.requires
+                .iter()
+                .map(|req| self.depth(req) + 1)
+                .max()
+                .unwrap_or(0)
+        })
+    }
+
+    pub fn state(&self, id: &str) -> TechState {
+        if self.done.contains(id) {
+            TechState::Done
+        } else if self.active.as_ref().is_some_and(|(active, _)| active == id) {
+            TechState::Researching
+        } else if self
+            .tech(id)
+            .is_some_and(|tech| tech.requires.iter().all(|req| self.done.contains(req)))
+        {
+            TechState::Available
+        } else {
+            TechState::Locked
+        }
+    }
+
+    fn unlocks(&self, researched_only: bool) -> impl Iterator<Item = &Unlock> {
+        self.techs
+            .iter()
+            .filter(move |tech| !researched_only || self.done.contains(&tech.id))
+            .flat_map(|tech| tech.unlocks.iter())
+    }
+
+    fn is_unlocked(&self, unlock: &Unlock) -> bool {
+        !self.unlocks(false).any(|u| u == unlock) || self.unlocks(true).any(|u| u == unlock)
+    }
+
+    pub fn is_action_unlocked(&self, action: &Action) -> bool {
+        self.is_unlocked(&Unlock::Action(action.clone()))
+    }
+
+    pub fn tower_level_cap(&self) -> usize {
+        self.unlocks(true)
+            .filter_map(|unlock| match unlock {
+                Unlock::TowerLevelCap(cap) => Some(*cap),
+                _ => None,
+            })
+            .fold(BASE_TOWER_LEVEL_CAP, usize::max)
+    }
+
+    pub fn farm_level_cap(&self) -> usize {
+        self.unlocks(true)
+            .filter_map(|unlock| match unlock {
+                Unlock::FarmLevelCap(cap) => Some(*cap),
+                _ => None,
+            })
+            .fold(BASE_FARM_LEVEL_CAP, usize::max)
+    }
+
+    /// Farm output after every researched efficiency bonus.
+    pub fn farm_yield(&self, base: usize) -> usize {
+        let bonus = self
+            .unlocks(true)
+            .filter_map(|unlock| match unlock {
+                Unlock::FarmEfficiency(bonus) => Some(*bonus),
+                _ => None,
+            })
+            .sum::<f32>();
+        (base as f32 * (1.0 + bonus)) as usize
+    }
+
+    /// Whether researched techs permit building `tile_type`.
+    pub fn allows(&self, tile_type: &TileType) -> bool {
+        match tile_type {
+            TileType::Tile(..) => tile_type.get_level() <= self.tower_level_cap(),
+            TileType::Farm(_, resource) => {
+                tile_type.get_level() <= self.farm_level_cap()
+                    && self.is_unlocked(&Unlock::Farming(*resource))
+            }
+            _ => true,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct ResearchPanel;
+
+#[derive(Component)]
+pub struct TechButton(String);
+
+fn make_research_ui(mut commands: Commands, research: Res<Research>) {
+    let depth = research
+        .techs
+        .iter()
+        .map(|tech| research.depth(&tech.id))
+        .max()
+        .unwrap_or(0);
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Percent(10.0),
+                    top: Val::Percent(10.0),
+                    column_gap: Val::Px(24.0),
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            ResearchPanel,
+            Interaction::default(),
+        ))
+        .with_children(|panel| {
+            // One column per tier, so prerequisites are always left of what they unlock
+            (0..=depth).for_each(|tier| {
+                panel
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            justify_content: JustifyContent::Center,
+                            row_gap: Val::Px(12.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|column| {
+                        research
+                            .techs
+                            .iter()
+                            .filter(|tech| research.depth(&tech.id) == tier)
+                            .for_each(|tech| {
+                                column
+                                    .spawn((
+                                        ButtonBundle {
+                                            style: Style {
+                                                width: Val::Px(160.0),
+                                                padding: UiRect::all(Val::Px(6.0)),
+                                                ..default()
+                                            },
+                                            ..default()
+                                        },
+                                        TechButton(tech.id.clone()),
+                                    ))
+                                    .with_children(|button| {
+                                        button.spawn(TextBundle::from_section(
+                                            "",
+                                            TextStyle {
+                                                font_size: 14.0,
+                                                ..default()
+                                            },
+                                        ));
+                                    });
+                            });
+                    });
+            });
+        });
+}
+
+fn toggle_research_ui(
+    inputs: ActionInput,
+    mut q_panel: Query<&mut Visibility, With<ResearchPanel>>,
+) {
+    if !inputs.just_pressed(InputAction::ToggleResearch) {
+        return;
+    }
+    q_panel.iter_mut().for_each(|mut visibility| {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    })
+}
+
+fn start_research(
+    q_buttons: Query<(&Interaction, &TechButton), Changed<Interaction>>,
+    mut research: ResMut<Research>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .for_each(|(_, TechButton(id))| {
+            if research.state(id) != TechState::Available {
+                return;
+            }
+            if research.active.is_some() {
+                notify_queue.push(
+                    Notification::new(Category::Research, locale.t("research-busy")).warning(),
+                );
+                return;
+            }
+            let Some(tech) = research.tech(id) else {
+                return;
+            };
+            let (cost, duration) = (tech.cost, tech.duration);
+            if currency.wood < cost {
+                notify_queue.push(
+                    Notification::new(
+                        Category::Research,
+                        locale.fmt("research-short", &[("tech", &locale.t(&tech.key()))]),
+                    )
+                    .warning(),
+                );
+                return;
+            }
+            currency.wood -= cost;
+            ledger.record(
+                LedgerCategory::Research,
+                ResourceType::Wood,
+                -(cost as isize),
+            );
+            research.active = Some((id.clone(), Timer::from_seconds(duration, TimerMode::Once)));
+        })
+}
+
+fn progress_research(
+    mut research: ResMut<Research>,
+    locale: Res<Locale>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    time: Res<Time>,
+) {
+    let Some((id, timer)) = research.active.as_mut() else {
+        return;
+    };
+    if !timer.tick(time.delta()).finished() {
+        return;
+    }
+    let id = id.clone();
+    notify_queue.push(Notification::new(
+        Category::Research,
+        locale.fmt(
+            "research-complete",
+            &[(
+                "tech",
+                &research
+                    .tech(&id)
+                    .map_or(id.clone(), |tech| locale.t(&tech.key())),
+            )],
+        ),
+    ));
+    research.active = None;
+    research.done.insert(id);
+}
+
+fn update_research_ui(
+    research: Res<Research>,
+    locale: Res<Locale>,
+    mut q_buttons: Query<(&TechButton, &Children, &mut BackgroundColor)>,
+    mut q_text: Query<&mut Text>,
+) {
+    q_buttons
+        .iter_mut()
+        .for_each(|(TechButton(id), children, mut background)| {
+            let Some(tech) = research.tech(id) else {
+                return;
+            };
+            let state = research.state(id);
+            *background = match state {
+                TechState::Locked => Color::srgb(0.15, 0.15, 0.15),
+                TechState::Available => Color::srgb(0.2, 0.3, 0.5),
+                TechState::Researching => Color::srgb(0.5, 0.4, 0.1),
+                TechState::Done => Color::srgb(0.1, 0.4, 0.1),
+            }
+            .into();
+
+            let status = match (state, &research.active) {
+                (TechState::Researching, Some((_, timer))) => {
+                    format!("{:.0}%", timer.fraction() * 100.0)
+                }
+                (TechState::Done, _) => locale.t("research-done"),
+                _ => locale.fmt(
+                    "research-cost",
+                    &[
+                        ("amount", &locale.number(tech.cost)),
+                        ("seconds", &locale.number(tech.duration)),
+                    ],
+                ),
+            };
+            let mut text = format!("{}\n{}", locale.t(&tech.key()), status);
+            if !tech.requires.is_empty() {
+                let requires = tech
+                    .requires
+                    .iter()
+                    .map(|req| {
+                        research
+                            .tech(req)
+                            .map_or(req.clone(), |t| locale.t(&t.key()))
+                    })
+                    .collect::<Vec<_>>();
+                text += "\n";
+                text += &locale.fmt("research-requires", &[("techs", &requires.join(", "))]);
+            }
+
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut label) = texts.fetch_next() {
+                label.sections[0].value.clone_from(&text);
+            }
+        })
+}
@@ -1,35 +1,94 @@
-use std::ops::Deref;
-
-use bevy::prelude::*;
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
 use bevy_mod_picking::prelude::*;
 use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 use crate::{
+    build_queue::QueueRequest,
+    camera::{GameCamera, PanOrbitState},
     constants::{
-        COST_SCALING, FARM_COST_SCALING, MODIFY_PENALTY, TEXTURE_MAP, TILE_COST_SCALING, TILE_SIZE,
+        BOX_SELECT_THRESHOLD, COST_SCALING, FARM_COST_SCALING, FARM_PRODUCTION, MODIFY_PENALTY,
+        SELL_RATIO, TEXTURE_MAP, TILE_COST_SCALING, TILE_SIZE, TILE_SPACING, TOWER_RANGE,
+        TOWER_RANGE_PER_LEVEL, TOWER_UPKEEP,
     },
-    currency::Currency,
-    util::NotifyQueue,
-    world::Position,
+    context_menu::OpenContextMenu,
+    currency::{Currency, Ledger, LedgerCategory},
+    game::GameState,
+    history::{History, TileChange},
+    input_map::{ActionInput, InputAction},
+    locale::Locale,
+    research::Research,
+    ui::cursor_over_ui,
+    util::{Category, Notification, NotifyQueue},
+    world::{tile_center, Position},
 };
 
 pub struct SelectTilePlugin;
 
 impl Plugin for SelectTilePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<TileSelectEvent>().add_systems(
-            Update,
-            (
-                select_tile.run_if(on_event::<TileSelectEvent>()),
-                upgrade_selected,
-            ),
-        );
+        app.add_event::<TileSelectEvent>()
+            .add_event::<ActionRequest>()
+            .init_resource::<BoxSelect>()
+            .init_resource::<PendingAction>()
+            .init_resource::<SelectionCursor>()
+            .add_systems(Startup, make_box_select_ui)
+            .add_systems(
+                Update,
+                (
+                    (
+                        box_select,
+                        select_tile.run_if(on_event::<TileSelectEvent>()),
+                        navigate_selection,
+                    )
+                        .chain(),
+                    (read_action_keys, upgrade_selected, sync_tile_visuals).chain(),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 #[derive(Component, Clone)]
 pub struct Selected(pub bool);
 
+/// Wood spent building the tile up since it was last sold.
+#[derive(Component, Debug, Default)]
+pub struct Invested(pub usize);
+
+impl Invested {
+    /// Wood refunded for selling the tile.
+    pub(crate) fn sell_value(&self) -> usize {
+        (self.0 as f32 * SELL_RATIO) as usize
+    }
+}
+
+/// An action to apply to every selected tile, or only to `tile` when it is set.
+#[derive(Event, Debug, Clone)]
+pub struct ActionRequest {
+    pub action: Action,
+    pub tile: Option<Entity>,
+}
+
+impl ActionRequest {
+    pub fn selected(action: Action) -> Self {
+        Self { action, tile: None }
+    }
+}
+
+/// Tile that keyboard and gamepad navigation moves from, last clicked or navigated to.
+#[derive(Resource, Debug, Default)]
+pub struct SelectionCursor(Option<Entity>);
+
+/// Action previewed while the preview modifier is held, applied once confirmed.
+#[derive(Resource, Debug, Default)]
+pub struct PendingAction(pub Option<Action>);
+
 #[derive(Event, Debug)]
 pub struct TileSelectEvent(Entity);
 
@@ -39,30 +98,248 @@ impl From<ListenerInput<Pointer<Click>>> for TileSelectEvent {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn select_tile(
     mut clicked: EventReader<TileSelectEvent>,
     q_collider: Query<&Parent>,
     mut q_selected: Query<(Entity, &mut Selected, &Position)>,
+    box_select: Res<BoxSelect>,
+    inputs: ActionInput,
+    q_interaction: Query<&Interaction>,
+    mut cursor: ResMut<SelectionCursor>,
+    mut open_menu: EventWriter<OpenContextMenu>,
     mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
 ) {
+    // Tiles are picked through the UI, so a click on a button would also land on the tile behind
+    // it. A drag that ends on the tile it started on is a box selection, not a click.
+    if box_select.pressed_on_ui || cursor_over_ui(&q_interaction) || box_select.finished {
+        clicked.clear();
+        return;
+    }
+    let add = inputs.pressed(InputAction::AddToSelection);
     clicked
         .read()
         .filter_map(|ev| q_collider.get(ev.0).ok())
         .for_each(|parent| {
             q_selected.iter_mut().for_each(|(ent, mut sel, position)| {
-                sel.0 = ent == parent.get();
-                sel.0
-                    .then(|| notify_queue.push(format!("Tile selected: {:?}", position)));
+                let clicked = ent == parent.get();
+                sel.0 = clicked || (add && sel.0);
+                if clicked {
+                    cursor.0 = Some(ent);
+                    if !add {
+                        open_menu.send(OpenContextMenu(ent));
+                    }
+                    notify_queue.push(
+                        Notification::new(
+                            Category::Selection,
+                            locale.fmt(
+                                "selection-tile",
+                                &[
+                                    ("x", &locale.number(position.0.x)),
+                                    ("z", &locale.number(position.0.z)),
+                                ],
+                            ),
+                        )
+                        .with_source(ent),
+                    );
+                }
             });
         })
 }
 
+/// Moves the selection to the neighboring tile, relative to the camera's facing.
+fn navigate_selection(
+    inputs: ActionInput,
+    mut cursor: ResMut<SelectionCursor>,
+    q_camera: Query<(&Transform, &PanOrbitState), With<GameCamera>>,
+    mut q_selected: Query<(Entity, &mut Selected, &Position)>,
+) {
+    let Some(step) = [
+        (InputAction::NavUp, Vec2::Y),
+        (InputAction::NavDown, Vec2::NEG_Y),
+        (InputAction::NavLeft, Vec2::NEG_X),
+        (InputAction::NavRight, Vec2::X),
+    ]
+    .into_iter()
+    .find_map(|(action, step)| inputs.just_pressed(action).then_some(step)) else {
+        return;
+    };
+    let Ok((transform, state)) = q_camera.get_single() else {
+        return;
+    };
+
+    // Snap the camera's ground-plane axes to the tile grid
+    let snap = |dir: Vec3| match dir.x.abs() > dir.z.abs() {
+        true => Vec3::X * dir.x.signum(),
+        false => Vec3::Z * dir.z.signum(),
+    };
+    let forward = snap(*transform.forward());
+    let right = snap(*transform.right());
+    let offset = forward * step.y + right * step.x;
+
+    // Start from the cursor, falling back to the tile nearest the camera's focus
+    let from = cursor
+        .0
+        .and_then(|entity| q_selected.get(entity).ok())
+        .map(|(_, _, position)| position.0)
+        .or_else(|| {
+            q_selected
+                .iter()
+                .map(|(_, _, position)| position.0)
+                .min_by(|a, b| {
+                    let focus = state.center / TILE_SPACING;
+                    a.distance(focus).total_cmp(&b.distance(focus))
+                })
+        });
+    let Some(from) = from else {
+        return;
+    };
+    let target = match cursor.0 {
+        Some(_) => from + offset,
+        None => from,
+    };
+    let Some(next) = q_selected
+        .iter()
+        .find(|(_, _, position)| position.0 == target)
+        .map(|(entity, ..)| entity)
+    else {
+        return;
+    };
+
+    let add = inputs.pressed(InputAction::AddToSelection);
+    cursor.0 = Some(next);
+    q_selected.iter_mut().for_each(|(entity, mut sel, _)| {
+        let selected = entity == next || (add && sel.0);
+        if sel.0 != selected {
+            sel.0 = selected;
+        }
+    });
+}
+
+/// Screen space rectangle dragged out with the left mouse button.
+#[derive(Resource, Debug, Default)]
+pub struct BoxSelect {
+    start: Option<Vec2>,
+    /// The button went down over the UI, so the click belongs to the UI even if it has since
+    /// been despawned, as the radial menu is.
+    pressed_on_ui: bool,
+    finished: bool,
+}
+
+impl BoxSelect {
+    /// Whether a drag that may become a box selection is in progress.
+    pub fn active(&self) -> bool {
+        self.start.is_some()
+    }
+}
+
+#[derive(Component)]
+pub struct BoxSelectRect;
+
+fn make_box_select_ui(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(Val::Px(1.0)),
+                ..default()
+            },
+            border_color: Color::WHITE.into(),
+            background_color: Color::srgba(1.0, 1.0, 1.0, 0.1).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        BoxSelectRect,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
+fn box_select(
+    mut box_select: ResMut<BoxSelect>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut evr_motion: EventReader<MouseMotion>,
+    mut evr_scroll: EventReader<MouseWheel>,
+    inputs: ActionInput,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    q_interaction: Query<&Interaction>,
+    mut q_rect: Query<(&mut Style, &mut Visibility), With<BoxSelectRect>>,
+    mut q_selected: Query<(&mut Selected, &GlobalTransform)>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+) {
+    box_select.finished = false;
+    // Read every frame, so only this frame's movement counts
+    let moved = evr_motion.read().map(|ev| ev.delta).sum::<Vec2>() != Vec2::ZERO;
+    let scrolled = evr_scroll.read().count() > 0;
+    let Some(cursor) = q_window
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+    else {
+        return;
+    };
+
+    if mouse.just_pressed(MouseButton::Left) {
+        let over_ui = cursor_over_ui(&q_interaction);
+        box_select.pressed_on_ui = over_ui;
+        // The camera keys double as modifiers, so they only count while the camera moves
+        let moving_camera = (moved || scrolled)
+            && [InputAction::Pan, InputAction::Orbit, InputAction::Zoom]
+                .into_iter()
+                .any(|action| inputs.pressed(action));
+        box_select.start = (!over_ui && !moving_camera).then_some(cursor);
+    }
+    let Some(start) = box_select.start else {
+        return;
+    };
+    let rect = Rect::from_corners(start, cursor);
+    let dragging = rect.size().max_element() > BOX_SELECT_THRESHOLD;
+
+    if let Ok((mut style, mut visibility)) = q_rect.get_single_mut() {
+        *visibility = match dragging && mouse.pressed(MouseButton::Left) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        style.left = Val::Px(rect.min.x);
+        style.top = Val::Px(rect.min.y);
+        style.width = Val::Px(rect.width());
+        style.height = Val::Px(rect.height());
+    }
+
+    if !mouse.just_released(MouseButton::Left) {
+        return;
+    }
+    box_select.start = None;
+    if !dragging {
+        return;
+    }
+    box_select.finished = true;
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+    let add = inputs.pressed(InputAction::AddToSelection);
+    let mut count = 0;
+    q_selected.iter_mut().for_each(|(mut sel, transform)| {
+        let inside = camera
+            .world_to_viewport(camera_transform, tile_center(transform.translation()))
+            .is_some_and(|pos| rect.contains(pos));
+        sel.0 = inside || (add && sel.0);
+        count += sel.0 as usize;
+    });
+    notify_queue.push(Notification::new(
+        Category::Selection,
+        locale.fmt("selection-count", &[("count", &locale.number(count))]),
+    ));
+}
+
 pub trait Level {
     fn next(&mut self);
     fn cost(&self) -> usize;
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 pub struct FarmLevel(usize);
 
 impl From<usize> for FarmLevel {
@@ -81,7 +358,7 @@ impl Level for FarmLevel {
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 pub struct TileLevel(usize);
 
 impl From<usize> for TileLevel {
@@ -100,30 +377,75 @@ impl Level for TileLevel {
     }
 }
 
-#[derive(PartialEq, Debug, Clone, Eq, Hash)]
+#[derive(PartialEq, Debug, Clone, Copy, Eq, Hash, Serialize, Deserialize)]
 pub enum ResourceType {
     Wood,
+    Stone,
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 2] = [ResourceType::Wood, ResourceType::Stone];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceType::Wood => "wood",
+            ResourceType::Stone => "stone",
+        }
+    }
+
+    /// Message catalog key for the resource's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            ResourceType::Wood => "resource-wood",
+            ResourceType::Stone => "resource-stone",
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub enum Action {
     MakeFarm,
     MakeTile,
+    Sell,
 }
 
-impl TryFrom<KeyCode> for Action {
+impl Action {
+    pub const ALL: [Action; 3] = [Action::MakeFarm, Action::MakeTile, Action::Sell];
+
+    /// Message catalog key for the action's name.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Action::MakeFarm => "action-make-farm",
+            Action::MakeTile => "action-make-tile",
+            Action::Sell => "action-sell",
+        }
+    }
+}
+
+impl TryFrom<InputAction> for Action {
     type Error = ();
 
-    fn try_from(value: KeyCode) -> Result<Self, Self::Error> {
+    fn try_from(value: InputAction) -> Result<Self, Self::Error> {
         match value {
-            KeyCode::KeyF => Ok(Self::MakeFarm),
-            KeyCode::KeyU => Ok(Self::MakeTile),
+            InputAction::MakeFarm => Ok(Self::MakeFarm),
+            InputAction::MakeTile => Ok(Self::MakeTile),
+            InputAction::Sell => Ok(Self::Sell),
             _ => Err(()),
         }
     }
 }
 
-#[derive(Component, PartialEq, Debug, Eq, Hash, Clone)]
+impl From<&Action> for InputAction {
+    fn from(value: &Action) -> Self {
+        match value {
+            Action::MakeFarm => InputAction::MakeFarm,
+            Action::MakeTile => InputAction::MakeTile,
+            Action::Sell => InputAction::Sell,
+        }
+    }
+}
+
+#[derive(Component, PartialEq, Debug, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum TileType {
     Floor,
     Resource(ResourceType),
@@ -132,7 +454,7 @@ pub enum TileType {
 }
 
 impl TileType {
-    fn get_level(&self) -> usize {
+    pub(crate) fn get_level(&self) -> usize {
         match self {
             TileType::Tile(level) => level.0,
             TileType::Farm(level, ..) => level.0,
@@ -142,9 +464,8 @@ impl TileType {
 
     fn next_cost(&self, action: &Action) -> Option<usize> {
         match self {
-            TileType::Tile(..) if action == &Action::MakeFarm => {
-                Some((FARM_COST_SCALING as f32 * MODIFY_PENALTY).ceil() as usize)
-            }
+            // Towers stand on bare floor, so there is no resource to turn one into a farm. A farm
+            // can still be torn down for a tower, at a penalty.
             TileType::Farm(..) if action == &Action::MakeTile => {
                 Some((TILE_COST_SCALING as f32 * MODIFY_PENALTY).ceil() as usize)
             }
@@ -163,30 +484,110 @@ impl TileType {
         }
     }
 
-    fn get_action_cost(&self, action: &Action) -> usize {
+    pub(crate) fn get_action_cost(&self, action: &Action) -> usize {
         self.next_cost(action).unwrap_or(self.upgrade_cost(action))
     }
 
-    fn try_upgrade(&mut self, currency_amount: usize, key: KeyCode) -> Option<usize> {
-        let Ok(action) = Action::try_from(key) else {
-            return None;
+    /// The tile this one turns into when `action` is applied, if the action applies to it.
+    pub(crate) fn after_action(&self, action: &Action) -> Option<TileType> {
+        match (self, action) {
+            (TileType::Tile(level), Action::MakeTile) => {
+                let mut level = *level;
+                level.next();
+                Some(TileType::Tile(level))
+            }
+            (TileType::Farm(level, resource_type), Action::MakeFarm) => {
+                let mut level = *level;
+                level.next();
+                Some(TileType::Farm(level, *resource_type))
+            }
+            // Converting a farm gives up its resource
+            (TileType::Floor | TileType::Farm(..), Action::MakeTile) => {
+                Some(TileType::Tile(TileLevel(1)))
+            }
+            (TileType::Resource(resource_type), Action::MakeFarm) => {
+                Some(TileType::Farm(FarmLevel(1), *resource_type))
+            }
+            (TileType::Tile(..), Action::Sell) => Some(TileType::Floor),
+            (TileType::Farm(_, resource_type), Action::Sell) => {
+                Some(TileType::Resource(*resource_type))
+            }
+            _ => None,
+        }
+    }
+
+    /// Like [`TileType::after_action`], but only if research permits the action and its result.
+    pub(crate) fn available_action(
+        &self,
+        action: &Action,
+        research: &Research,
+    ) -> Option<TileType> {
+        self.after_action(action)
+            .filter(|next| research.is_action_unlocked(action) && research.allows(next))
+    }
+
+    /// Shortest sequence of build actions that turns this tile into `target`, if there is one.
+    /// Never sells, so a tile already past `target` can't reach it.
+    pub(crate) fn actions_to(&self, target: &TileType) -> Option<Vec<Action>> {
+        let mut frontier = VecDeque::from([(self.clone(), vec![])]);
+        while let Some((tile_type, actions)) = frontier.pop_front() {
+            if &tile_type == target {
+                return Some(actions);
+            }
+            [Action::MakeFarm, Action::MakeTile]
+                .into_iter()
+                .filter_map(|action| Some((tile_type.after_action(&action)?, action)))
+                .filter(|(next, _)| next.get_level() <= target.get_level())
+                .for_each(|(next, action)| {
+                    let mut actions = actions.clone();
+                    actions.push(action);
+                    frontier.push_back((next, actions));
+                });
+        }
+        None
+    }
+
+    /// Total cost of applying `actions` in order, starting from this tile.
+    pub(crate) fn actions_cost(&self, actions: &[Action]) -> usize {
+        actions
+            .iter()
+            .scan(self.clone(), |tile_type, action| {
+                let cost = tile_type.get_action_cost(action);
+                *tile_type = tile_type.after_action(action)?;
+                Some(cost)
+            })
+            .sum()
+    }
+
+    /// Scene for this tile. Every level of a farm or tower shares one model.
+    pub(crate) fn texture(&self) -> &'static str {
+        let key = match self {
+            TileType::Tile(_) => TileType::Tile(TileLevel(1)),
+            TileType::Farm(_, resource_type) => TileType::Farm(FarmLevel(1), *resource_type),
+            other => other.clone(),
         };
-        let cost = self.get_action_cost(&action);
-        info!("{}", cost);
-        (currency_amount >= cost).then(|| {
-            match self {
-                TileType::Tile(ref mut level) if action == Action::MakeTile => level.next(),
-                TileType::Farm(ref mut level, _) if action == Action::MakeFarm => level.next(),
-                TileType::Resource(resource_type) if action == Action::MakeFarm => {
-                    *self = TileType::Farm(FarmLevel(1), resource_type.clone());
-                }
-                TileType::Floor if action == Action::MakeTile => {
-                    *self = TileType::Tile(TileLevel(1));
-                }
-                _ => {}
-            };
-            cost
-        })
+        TEXTURE_MAP.get(&key).expect("No texture for tile!")
+    }
+
+    /// Name and level of the tile in the player's language.
+    pub(crate) fn localized(&self, locale: &Locale) -> String {
+        match self {
+            TileType::Floor => locale.t("tile-floor"),
+            TileType::Resource(resource_type) => locale.fmt(
+                "tile-resource",
+                &[("resource", &locale.t(resource_type.key()))],
+            ),
+            TileType::Farm(level, resource_type) => locale.fmt(
+                "tile-farm",
+                &[
+                    ("resource", &locale.t(resource_type.key())),
+                    ("level", &locale.number(level.0)),
+                ],
+            ),
+            TileType::Tile(level) => {
+                locale.fmt("tile-tower", &[("level", &locale.number(level.0))])
+            }
+        }
     }
 
     #[inline]
@@ -205,6 +606,25 @@ impl TileType {
         }
     }
 
+    /// Resource and amount paid out per production tick.
+    pub(crate) fn production(&self) -> Option<(ResourceType, usize)> {
+        match self {
+            TileType::Farm(level, resource) => Some((*resource, FARM_PRODUCTION * level.0)),
+            _ => None,
+        }
+    }
+
+    /// Attack radius of a tower, growing with its level.
+    pub(crate) fn range(&self) -> Option<f32> {
+        self.tile()
+            .map(|level| TOWER_RANGE + TOWER_RANGE_PER_LEVEL * (level - 1) as f32)
+    }
+
+    /// Currency charged per production tick to keep a tower running.
+    pub(crate) fn upkeep(&self) -> usize {
+        self.tile().map_or(0, |level| TOWER_UPKEEP * level)
+    }
+
     #[inline]
     pub(crate) fn is_floor(&self) -> bool {
         matches!(self, TileType::Floor)
@@ -212,17 +632,17 @@ impl TileType {
 
     #[inline]
     pub(crate) fn is_farm(&self) -> bool {
-        matches!(self, TileType::Tile(_))
+        matches!(self, TileType::Farm(_, _))
     }
 
     #[inline]
     pub(crate) fn is_tile(&self) -> bool {
-        matches!(self, TileType::Farm(_, _))
+        matches!(self, TileType::Tile(_))
     }
 
     pub(crate) fn get_collider_height(&self) -> f32 {
         match self {
-            Self::Resource(ResourceType::Wood) => 10.1,
+            Self::Resource(..) => 10.1,
             Self::Tile(..) => 9.1,
             Self::Farm(..) => 4.1,
             _ => 2.1,
@@ -230,36 +650,184 @@ impl TileType {
     }
 }
 
-pub fn upgrade_selected(
-    mut q_selected: Query<(&Selected, Entity, &mut TileType, &mut Handle<Scene>)>,
+/// Keeps each tile's scene and collider in line with its [`TileType`].
+pub(crate) fn sync_tile_visuals(
+    mut q_tiles: Query<(Entity, &TileType, &mut Handle<Scene>), Changed<TileType>>,
     mut q_collider: Query<(&mut Collider, &mut Transform, &Parent)>,
     asset_server: Res<AssetServer>,
-    keys: Res<ButtonInput<KeyCode>>,
-    mut currency: ResMut<Currency>,
 ) {
-    currency.wood -= q_selected
+    q_tiles
         .iter_mut()
-        .find(|(sel, ..)| sel.0)
-        .and_then(|(_, entity, mut tile_type, mut scene)| {
-            keys.get_just_pressed().next().and_then(|k| {
-                tile_type.try_upgrade(currency.wood, *k).inspect(|_| {
-                    *scene = asset_server.load(
-                        TEXTURE_MAP
-                            .get(tile_type.deref())
-                            .expect("No texture for tile!"),
-                    );
+        .for_each(|(entity, tile_type, mut scene)| {
+            let handle = asset_server.load(tile_type.texture());
+            // Reassigning the same handle would respawn the scene
+            if *scene != handle {
+                *scene = handle;
+            }
 
-                    if let Some((mut collider, mut transform, ..)) =
-                        q_collider.iter_mut().find(|(.., p)| p.get() == entity)
-                    {
-                        let height = tile_type.get_collider_height();
-                        if let Some(mut cuboid) = collider.as_cuboid_mut() {
-                            cuboid.set_half_extents((TILE_SIZE, height, TILE_SIZE).into());
-                        }
-                        transform.translation.y = height;
-                    }
-                })
-            })
+            if let Some((mut collider, mut transform, ..)) =
+                q_collider.iter_mut().find(|(.., p)| p.get() == entity)
+            {
+                let height = tile_type.get_collider_height();
+                if let Some(mut cuboid) = collider.as_cuboid_mut() {
+                    cuboid.set_half_extents((TILE_SIZE, height, TILE_SIZE).into());
+                }
+                transform.translation.y = height;
+            }
         })
-        .unwrap_or(0)
+}
+
+fn read_action_keys(
+    inputs: ActionInput,
+    research: Res<Research>,
+    locale: Res<Locale>,
+    mut pending: ResMut<PendingAction>,
+    mut requests: EventWriter<ActionRequest>,
+    mut queue_requests: EventWriter<QueueRequest>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    let previewing = inputs.pressed(InputAction::Preview);
+    if !previewing && pending.0.is_some() {
+        pending.0 = None;
+    }
+    if previewing && inputs.just_pressed(InputAction::Confirm) {
+        if let Some(action) = pending.0.take() {
+            requests.send(ActionRequest::selected(action));
+        }
+        return;
+    }
+
+    let Some(action) = inputs
+        .all_just_pressed()
+        .find_map(|input| Action::try_from(input).ok())
+    else {
+        return;
+    };
+    if !research.is_action_unlocked(&action) {
+        notify_queue.push(
+            Notification::new(
+                Category::Research,
+                locale.fmt(
+                    "research-action-locked",
+                    &[("action", &locale.t(action.key()))],
+                ),
+            )
+            .warning(),
+        );
+        return;
+    }
+    if inputs.pressed(InputAction::QueueAction) {
+        queue_requests.send(QueueRequest(action));
+        return;
+    }
+    match previewing {
+        true => pending.0 = Some(action),
+        false => {
+            requests.send(ActionRequest::selected(action));
+        }
+    }
+}
+
+/// Applies `action` to a tile if research allows it and it is affordable, paying for it.
+///
+/// The tile is only borrowed mutably once the action goes through, so a refused action doesn't
+/// mark it changed.
+pub(crate) fn apply_action(
+    entity: Entity,
+    tile_type: &mut Mut<TileType>,
+    invested: &mut Mut<Invested>,
+    action: &Action,
+    research: &Research,
+    currency: &mut Currency,
+    ledger: &mut Ledger,
+) -> Option<TileChange> {
+    let after = tile_type.available_action(action, research)?;
+    let cost = tile_type.get_action_cost(action);
+    if currency.wood < cost {
+        return None;
+    }
+    let refund = match action {
+        Action::Sell => invested.sell_value(),
+        _ => 0,
+    };
+    let before = std::mem::replace(tile_type.as_mut(), after);
+    currency.wood = currency.wood - cost + refund;
+    ledger.record(
+        LedgerCategory::Construction,
+        ResourceType::Wood,
+        -(cost as isize),
+    );
+    ledger.record(LedgerCategory::Sales, ResourceType::Wood, refund as isize);
+    let invested_before = invested.0;
+    invested.0 = match action {
+        Action::Sell => 0,
+        _ => invested.0 + cost,
+    };
+    Some(TileChange {
+        entity,
+        before,
+        after: tile_type.clone(),
+        cost,
+        refund,
+        invested_before,
+        invested_after: invested.0,
+    })
+}
+
+/// Applies each requested action to its tiles, cheapest first, until funds run out.
+#[allow(clippy::too_many_arguments)]
+pub fn upgrade_selected(
+    mut requests: EventReader<ActionRequest>,
+    mut q_selected: Query<(&Selected, Entity, &mut TileType, &mut Invested)>,
+    research: Res<Research>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    mut history: ResMut<History>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+) {
+    for ActionRequest { action, tile } in requests.read() {
+        let mut selected = q_selected
+            .iter_mut()
+            .filter(|(sel, entity, ..)| tile.map_or(sel.0, |tile| tile == *entity))
+            .map(|(_, entity, tile_type, invested)| {
+                (
+                    tile_type.get_action_cost(action),
+                    entity,
+                    tile_type,
+                    invested,
+                )
+            })
+            .collect::<Vec<_>>();
+        selected.sort_by_key(|(cost, ..)| *cost);
+
+        let total = selected.len();
+        let changes = selected
+            .into_iter()
+            .filter_map(|(_, entity, mut tile_type, mut invested)| {
+                apply_action(
+                    entity,
+                    &mut tile_type,
+                    &mut invested,
+                    action,
+                    &research,
+                    &mut currency,
+                    &mut ledger,
+                )
+            })
+            .collect::<Vec<_>>();
+        if total > 1 {
+            notify_queue.push(Notification::new(
+                Category::Construction,
+                locale.fmt(
+                    "construction-upgraded",
+                    &[
+                        ("done", &locale.number(changes.len())),
+                        ("total", &locale.number(total)),
+                    ],
+                ),
+            ));
+        }
+        history.record(changes);
+    }
 }
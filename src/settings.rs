@@ -0,0 +1,143 @@
+use std::fs;
+
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{RESOLUTIONS, SETTINGS_PATH, UI_SCALES, WINDOW_HEIGHT, WINDOW_TITLE, WINDOW_WIDTH},
+    locale::Language,
+};
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                apply_window_settings.run_if(resource_changed::<Settings>),
+                update_ui_scale
+                    .run_if(on_event::<WindowResized>().or_else(resource_changed::<Settings>)),
+            ),
+        );
+    }
+}
+
+/// Player preferences, loaded from and saved to [`SETTINGS_PATH`].
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub fullscreen: bool,
+    pub resolution: (f32, f32),
+    /// Multiplier on top of the scaling that keeps the UI proportional to the window.
+    pub ui_scale: f32,
+    pub language: Language,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            resolution: (WINDOW_WIDTH, WINDOW_HEIGHT),
+            ui_scale: 1.0,
+            language: Language::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(SETTINGS_PATH) else {
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid settings in {}: {}", SETTINGS_PATH, err);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) {
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|err| err.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = std::path::Path::new(SETTINGS_PATH).parent() {
+                    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+                }
+                fs::write(SETTINGS_PATH, contents).map_err(|err| err.to_string())
+            });
+        if let Err(err) = result {
+            warn!("Failed to save settings: {}", err);
+        }
+    }
+
+    fn mode(&self) -> WindowMode {
+        match self.fullscreen {
+            true => WindowMode::BorderlessFullscreen,
+            false => WindowMode::Windowed,
+        }
+    }
+
+    /// The primary window as it should open.
+    pub fn window(&self) -> Window {
+        Window {
+            title: WINDOW_TITLE.to_string(),
+            resolution: self.resolution.into(),
+            mode: self.mode(),
+            ..default()
+        }
+    }
+
+    /// Steps to the next resolution in [`RESOLUTIONS`], wrapping around.
+    pub fn cycle_resolution(&mut self) {
+        self.resolution = cycle(&RESOLUTIONS, self.resolution);
+    }
+
+    /// Steps to the next scale in [`UI_SCALES`], wrapping around.
+    pub fn cycle_ui_scale(&mut self) {
+        self.ui_scale = cycle(&UI_SCALES, self.ui_scale);
+    }
+
+    /// Steps to the next language in [`Language::ALL`], wrapping around.
+    pub fn cycle_language(&mut self) {
+        self.language = cycle(&Language::ALL, self.language);
+    }
+}
+
+/// The option after `current`, or the first one if `current` isn't listed.
+fn cycle<T: Copy + PartialEq>(options: &[T], current: T) -> T {
+    let next = options
+        .iter()
+        .position(|option| *option == current)
+        .map_or(0, |index| (index + 1) % options.len());
+    options[next]
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = q_window.get_single_mut() else {
+        return;
+    };
+    window.mode = settings.mode();
+    if !settings.fullscreen {
+        let (width, height) = settings.resolution;
+        window.resolution.set(width, height);
+    }
+}
+
+/// Scales the UI with the window, so layouts made for the default size keep their proportions.
+fn update_ui_scale(
+    settings: Res<Settings>,
+    mut ui_scale: ResMut<UiScale>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+    let fit = (window.width() / WINDOW_WIDTH).min(window.height() / WINDOW_HEIGHT);
+    ui_scale.0 = fit * settings.ui_scale;
+}
@@ -0,0 +1,207 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use itertools::iproduct;
+use serde::Serialize;
+
+use crate::{
+    constants::REPORTS_DIR,
+    currency::{Ledger, LedgerCategory},
+    game::GameState,
+    locale::Locale,
+    menu::{spawn_menu, MenuButton, MenuButtonPressed},
+    select_tile::{Invested, ResourceType, TileType},
+    util::{Category, Notification, NotifyQueue},
+    wave::{Phase, WaveState},
+    world::Position,
+};
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionStats>()
+            .add_systems(OnEnter(GameState::Summary), make_summary)
+            .add_systems(
+                Update,
+                (
+                    track_time_played.run_if(in_state(GameState::Playing)),
+                    save_report.run_if(in_state(GameState::Summary)),
+                ),
+            );
+    }
+}
+
+/// Running totals for the current game that nothing else keeps track of.
+#[derive(Resource, Debug, Default)]
+pub struct SessionStats {
+    /// Seconds spent playing, not counting menus or pauses.
+    pub time_played: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LedgerLine {
+    pub category: LedgerCategory,
+    pub resource: ResourceType,
+    pub earned: usize,
+    pub spent: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TowerSummary {
+    pub x: f32,
+    pub z: f32,
+    pub level: usize,
+    pub invested: usize,
+}
+
+/// Everything shown on the end of game screen, and written out as the JSON report.
+#[derive(Resource, Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub waves_survived: usize,
+    pub time_played: f32,
+    pub ledger: Vec<LedgerLine>,
+    /// The tower with the most wood invested in it.
+    pub most_valuable_tower: Option<TowerSummary>,
+}
+
+impl SessionSummary {
+    fn describe(&self, locale: &Locale) -> Vec<String> {
+        let seconds = self.time_played as usize;
+        let mut lines = vec![
+            locale.fmt(
+                "summary-waves",
+                &[("count", &locale.number(self.waves_survived))],
+            ),
+            locale.fmt(
+                "summary-time",
+                &[
+                    ("minutes", &locale.number(seconds / 60)),
+                    ("seconds", &format!("{:02}", seconds % 60)),
+                ],
+            ),
+            locale.t("summary-ledger"),
+        ];
+        lines.extend(self.ledger.iter().map(|line| {
+            locale.fmt(
+                "summary-ledger-line",
+                &[
+                    ("category", &locale.t(line.category.key())),
+                    ("earned", &locale.number(line.earned)),
+                    ("spent", &locale.number(line.spent)),
+                    ("resource", &locale.t(line.resource.key())),
+                ],
+            )
+        }));
+        lines.push(match &self.most_valuable_tower {
+            Some(tower) => locale.fmt(
+                "summary-tower",
+                &[
+                    ("level", &locale.number(tower.level)),
+                    ("x", &locale.number(tower.x)),
+                    ("z", &locale.number(tower.z)),
+                    ("amount", &locale.number(tower.invested)),
+                ],
+            ),
+            None => locale.t("summary-no-tower"),
+        });
+        lines
+    }
+}
+
+fn track_time_played(time: Res<Time>, mut stats: ResMut<SessionStats>) {
+    stats.time_played += time.delta_seconds();
+}
+
+fn make_summary(
+    mut commands: Commands,
+    stats: Res<SessionStats>,
+    ledger: Res<Ledger>,
+    wave_state: Res<WaveState>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&TileType, &Position, &Invested)>,
+) {
+    let summary = SessionSummary {
+        // A wave still running when the game ends wasn't survived
+        waves_survived: match wave_state.phase {
+            Phase::Build => wave_state.wave,
+            Phase::Wave => wave_state.wave.saturating_sub(1),
+        },
+        time_played: stats.time_played,
+        ledger: iproduct!(LedgerCategory::ALL, ResourceType::ALL)
+            .map(|(category, resource)| LedgerLine {
+                category,
+                resource,
+                earned: ledger.earned(category, resource),
+                spent: ledger.spent(category, resource),
+            })
+            .filter(|line| line.earned > 0 || line.spent > 0)
+            .collect(),
+        most_valuable_tower: q_tiles
+            .iter()
+            .filter(|(tile_type, ..)| tile_type.is_tile())
+            .max_by_key(|(_, _, invested)| invested.0)
+            .map(|(tile_type, position, invested)| TowerSummary {
+                x: position.0.x,
+                z: position.0.z,
+                level: tile_type.get_level(),
+                invested: invested.0,
+            }),
+    };
+
+    let menu = spawn_menu(
+        &mut commands,
+        GameState::Summary,
+        "summary-title",
+        &[MenuButton::SaveReport, MenuButton::MainMenu],
+    );
+    let details = commands
+        .spawn(TextBundle::from_section(
+            summary.describe(&locale).join("\n"),
+            TextStyle::default(),
+        ))
+        .id();
+    // Between the title and the buttons
+    commands.entity(menu).insert_children(1, &[details]);
+    commands.insert_resource(summary);
+}
+
+fn save_report(
+    summary: Res<SessionSummary>,
+    locale: Res<Locale>,
+    mut pressed: EventReader<MenuButtonPressed>,
+    mut notify_queue: ResMut<NotifyQueue>,
+) {
+    if pressed
+        .read()
+        .filter(|MenuButtonPressed(button)| *button == MenuButton::SaveReport)
+        .count()
+        == 0
+    {
+        return;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let path = format!("{}/summary-{}.json", REPORTS_DIR, timestamp);
+    let result = serde_json::to_string_pretty(&*summary)
+        .map_err(|err| err.to_string())
+        .and_then(|contents| {
+            fs::create_dir_all(REPORTS_DIR).map_err(|err| err.to_string())?;
+            fs::write(&path, contents).map_err(|err| err.to_string())
+        });
+    notify_queue.push(match result {
+        Ok(()) => Notification::new(
+            Category::Stats,
+            locale.fmt("summary-saved", &[("path", &path)]),
+        ),
+        Err(err) => Notification::new(
+            Category::Stats,
+            locale.fmt("summary-save-failed", &[("error", &err)]),
+        )
+        .error(),
+    });
+}
@@ -1,18 +1,59 @@
-use crate::currency::Currency;
+use crate::{
+    camera::GameCamera,
+    constants::{POPUP_DURATION, POPUP_RISE},
+    currency::Currency,
+    game::GameState,
+    input_map::{InputAction, InputMap},
+    locale::Locale,
+    research::Research,
+    select_tile::{Action, ActionRequest, Invested, Selected, TileType},
+};
 use bevy::prelude::*;
 
 pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, make_ui);
+        app.add_systems(Startup, (make_ui, make_action_bar))
+            .add_systems(
+                Update,
+                (
+                    animate_floating_text,
+                    press_action_button,
+                    update_action_bar,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
 #[derive(Component)]
 pub struct CurrencyCounter;
 
-fn make_ui(mut commands: Commands, currency: Res<Currency>) {
+/// Wave number and countdown, updated by the wave plugin.
+#[derive(Component)]
+pub struct WaveCounter;
+
+/// Starts the next wave right away, for a bonus scaled by the build time left.
+#[derive(Component)]
+pub struct CallEarlyButton;
+
+/// Right-hand column of the HUD. Panels added to it stack below the wave counter.
+#[derive(Component)]
+pub struct SideColumn;
+
+/// Button applying an action to the selected tiles.
+#[derive(Component)]
+pub struct ActionButton(Action);
+
+/// Text anchored to a world position that drifts upwards and fades out.
+#[derive(Component)]
+pub struct FloatingText {
+    origin: Vec3,
+    timer: Timer,
+}
+
+pub fn make_ui(mut commands: Commands) {
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -24,10 +65,251 @@ fn make_ui(mut commands: Commands, currency: Res<Currency>) {
             ..default()
         })
         .with_children(|parent| {
+            // Filled in by the currency plugin, in the player's language
             parent.spawn((
-                TextBundle::from_section(format!("Wood: {}", currency.wood), TextStyle::default()),
+                TextBundle::from_section("", TextStyle::default()),
                 Label,
                 CurrencyCounter,
             ));
+            parent
+                .spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            align_self: AlignSelf::FlexStart,
+                            align_items: AlignItems::FlexEnd,
+                            row_gap: Val::Px(4.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    SideColumn,
+                ))
+                .with_children(|hud| {
+                    hud.spawn((
+                        TextBundle::from_section("", TextStyle::default()),
+                        Label,
+                        WaveCounter,
+                    ));
+                    hud.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(4.0)),
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                            ..default()
+                        },
+                        CallEarlyButton,
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section("", TextStyle::default()));
+                    });
+                });
+        });
+}
+
+fn make_action_bar(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|parent| {
+            Action::ALL.into_iter().for_each(|action| {
+                parent
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::all(Val::Px(8.0)),
+                                display: Display::None,
+                                ..default()
+                            },
+                            background_color: Color::srgb(0.2, 0.2, 0.2).into(),
+                            ..default()
+                        },
+                        ActionButton(action),
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section("", TextStyle::default()));
+                    });
+            });
         });
 }
+
+/// Shows a button for each action some selected tile can take, greyed out when even the
+/// cheapest of them is unaffordable. Runs every frame, so it only writes what changed.
+fn update_action_bar(
+    currency: Res<Currency>,
+    research: Res<Research>,
+    input_map: Res<InputMap>,
+    locale: Res<Locale>,
+    q_tiles: Query<(&Selected, &TileType, &Invested)>,
+    mut q_buttons: Query<(&ActionButton, &mut Style, &mut BackgroundColor, &Children)>,
+    mut q_text: Query<&mut Text>,
+) {
+    q_buttons.iter_mut().for_each(
+        |(ActionButton(action), mut style, mut background, children)| {
+            let targets = q_tiles
+                .iter()
+                .filter(|(sel, tile_type, _)| {
+                    sel.0 && tile_type.available_action(action, &research).is_some()
+                })
+                .map(|(_, tile_type, invested)| (tile_type, invested))
+                .collect::<Vec<_>>();
+            let Some(cheapest) = targets
+                .iter()
+                .map(|(tile_type, _)| tile_type.get_action_cost(action))
+                .min()
+            else {
+                if style.display != Display::None {
+                    style.display = Display::None;
+                }
+                return;
+            };
+            if style.display != Display::Flex {
+                style.display = Display::Flex;
+            }
+
+            let price = match action {
+                Action::Sell => locale.fmt(
+                    "price-refund",
+                    &[(
+                        "amount",
+                        &locale.number(
+                            targets
+                                .iter()
+                                .map(|(_, invested)| invested.sell_value())
+                                .sum::<usize>(),
+                        ),
+                    )],
+                ),
+                _ => locale.fmt(
+                    "price-cost",
+                    &[(
+                        "amount",
+                        &locale.number(
+                            targets
+                                .iter()
+                                .map(|(t, _)| t.get_action_cost(action))
+                                .sum::<usize>(),
+                        ),
+                    )],
+                ),
+            };
+            let key = input_map
+                .key(InputAction::from(action))
+                .map_or(locale.t("key-unbound"), |key| format!("{:?}", key));
+            let (fill, color) = match currency.wood >= cheapest {
+                true => (Color::srgb(0.2, 0.2, 0.2), Color::WHITE),
+                false => (Color::srgb(0.1, 0.1, 0.1), Color::srgb(0.5, 0.5, 0.5)),
+            };
+            if background.0 != fill {
+                background.0 = fill;
+            }
+            let label = locale.fmt(
+                "action-button",
+                &[
+                    ("action", &locale.t(action.key())),
+                    ("price", &price),
+                    ("key", &key),
+                ],
+            );
+            let mut texts = q_text.iter_many_mut(children);
+            while let Some(mut text) = texts.fetch_next() {
+                if text.sections[0].value != label {
+                    text.sections[0].value.clone_from(&label);
+                }
+                if text.sections[0].style.color != color {
+                    text.sections[0].style.color = color;
+                }
+            }
+        },
+    )
+}
+
+fn press_action_button(
+    currency: Res<Currency>,
+    research: Res<Research>,
+    q_tiles: Query<(&Selected, &TileType)>,
+    q_buttons: Query<(&Interaction, &ActionButton), Changed<Interaction>>,
+    mut requests: EventWriter<ActionRequest>,
+) {
+    q_buttons
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Pressed)
+        .filter(|(_, ActionButton(action))| {
+            q_tiles.iter().any(|(sel, tile_type)| {
+                sel.0
+                    && tile_type.available_action(action, &research).is_some()
+                    && currency.wood >= tile_type.get_action_cost(action)
+            })
+        })
+        .for_each(|(_, ActionButton(action))| {
+            requests.send(ActionRequest::selected(action.clone()));
+        })
+}
+
+/// Whether the cursor is over a button or panel, so clicks there shouldn't reach the tiles below.
+pub fn cursor_over_ui(q_interaction: &Query<&Interaction>) -> bool {
+    q_interaction
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+}
+
+pub fn spawn_floating_text(commands: &mut Commands, text: impl Into<String>, origin: Vec3) {
+    commands.spawn((
+        TextBundle::from_section(text, TextStyle::default()).with_style(Style {
+            position_type: PositionType::Absolute,
+            ..default()
+        }),
+        FloatingText {
+            origin,
+            timer: Timer::from_seconds(POPUP_DURATION, TimerMode::Once),
+        },
+    ));
+}
+
+fn animate_floating_text(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut FloatingText,
+        &mut Style,
+        &mut Text,
+        &mut Visibility,
+    )>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<GameCamera>>,
+    time: Res<Time>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+    query
+        .iter_mut()
+        .for_each(|(entity, mut popup, mut style, mut text, mut visibility)| {
+            if popup.timer.tick(time.delta()).finished() {
+                commands.entity(entity).despawn_recursive();
+                return;
+            }
+            let progress = popup.timer.fraction();
+            let position = popup.origin + Vec3::Y * POPUP_RISE * progress;
+            let Some(viewport) = camera.world_to_viewport(camera_transform, position) else {
+                *visibility = Visibility::Hidden;
+                return;
+            };
+            *visibility = Visibility::Inherited;
+            style.left = Val::Px(viewport.x);
+            style.top = Val::Px(viewport.y);
+            text.sections
+                .iter_mut()
+                .for_each(|section| section.style.color.set_alpha(1.0 - progress));
+        })
+}
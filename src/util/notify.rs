@@ -1,31 +1,330 @@
-use bevy::prelude::*;
+use std::collections::VecDeque;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::constants::{TOAST_DURATION, TOAST_MAX_VISIBLE};
 
 pub struct NotifyPlugin;
 
 impl Plugin for NotifyPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(NotifyQueue::default())
-            .add_systems(Update, notification_handler);
+            .init_resource::<NotifyFilter>()
+            .add_systems(Startup, make_toast_stack)
+            .add_systems(First, update_clock)
+            .add_systems(Update, (notification_handler, expire_toasts).chain());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn icon(&self) -> &'static str {
+        match self {
+            Severity::Info => "i",
+            Severity::Warning => "!",
+            Severity::Error => "x",
+        }
     }
+
+    fn color(&self) -> Color {
+        match self {
+            Severity::Info => Color::srgba(0.15, 0.15, 0.15, 0.8),
+            Severity::Warning => Color::srgba(0.5, 0.4, 0.05, 0.8),
+            Severity::Error => Color::srgba(0.55, 0.1, 0.1, 0.8),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Hover,
+    Selection,
+    Construction,
+    Economy,
+    Research,
+    Wave,
+    Settings,
+    Stats,
 }
 
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub category: Category,
+    pub severity: Severity,
+    pub message: String,
+    /// Seconds since startup, set when the notification is queued.
+    pub timestamp: f32,
+    pub source: Option<Entity>,
+    pub position: Option<Vec3>,
+}
+
+impl Notification {
+    pub fn new(category: Category, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            severity: Severity::Info,
+            message: message.into(),
+            timestamp: 0.0,
+            source: None,
+            position: None,
+        }
+    }
+
+    pub fn warning(self) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..self
+        }
+    }
+
+    pub fn error(self) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..self
+        }
+    }
+
+    pub fn with_source(self, source: Entity) -> Self {
+        Self {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    pub fn at(self, position: Vec3) -> Self {
+        Self {
+            position: Some(position),
+            ..self
+        }
+    }
+}
+
+/// Messages waiting to be shown, oldest first.
 #[derive(Debug, Resource, Clone, Default)]
 pub struct NotifyQueue {
-    _inner: Vec<String>,
+    _inner: VecDeque<Notification>,
+    now: f32,
 }
 
 impl NotifyQueue {
-    pub fn next(&mut self) -> Option<String> {
-        self._inner.pop()
+    pub fn next(&mut self) -> Option<Notification> {
+        self._inner.pop_front()
     }
 
-    pub fn push(&mut self, item: impl Into<String>) {
-        self._inner.push(item.into())
+    pub fn push(&mut self, notification: Notification) {
+        self._inner.push_back(Notification {
+            timestamp: self.now,
+            ..notification
+        })
     }
 }
 
-fn notification_handler(mut notify_queue: ResMut<NotifyQueue>) {
-    if let Some(message) = notify_queue.next() {
-        info!("{}", message);
+/// How often a category may show a toast. Muted categories are only logged at debug level.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CategoryRule {
+    pub min_interval: f32,
+    pub muted: bool,
+}
+
+#[derive(Resource, Debug)]
+pub struct NotifyFilter {
+    pub rules: HashMap<Category, CategoryRule>,
+}
+
+impl Default for NotifyFilter {
+    fn default() -> Self {
+        Self {
+            rules: [
+                // Fires on every tile the mouse crosses, so it only goes to the log
+                (
+                    Category::Hover,
+                    CategoryRule {
+                        min_interval: 0.0,
+                        muted: true,
+                    },
+                ),
+                (
+                    Category::Selection,
+                    CategoryRule {
+                        min_interval: 0.5,
+                        muted: false,
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl NotifyFilter {
+    fn rule(&self, category: Category) -> CategoryRule {
+        self.rules.get(&category).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Component)]
+pub struct ToastStack;
+
+/// A message on screen until its timer runs out. Repeats of it bump `count` instead.
+#[derive(Component)]
+pub struct Toast {
+    severity: Severity,
+    message: String,
+    count: usize,
+    timer: Timer,
+}
+
+impl Toast {
+    fn label(&self) -> String {
+        match self.count {
+            1 => format!("[{}] {}", self.severity.icon(), self.message),
+            count => format!("[{}] {} x{}", self.severity.icon(), self.message, count),
+        }
+    }
+}
+
+fn make_toast_stack(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            // Above the menus, so saving from them can be confirmed
+            z_index: ZIndex::Global(3),
+            ..default()
+        },
+        ToastStack,
+    ));
+}
+
+fn update_clock(time: Res<Time>, mut notify_queue: ResMut<NotifyQueue>) {
+    notify_queue.now = time.elapsed_seconds();
+}
+
+fn notification_handler(
+    mut commands: Commands,
+    mut notify_queue: ResMut<NotifyQueue>,
+    filter: Res<NotifyFilter>,
+    mut last_shown: Local<HashMap<Category, f32>>,
+    q_stack: Query<Entity, With<ToastStack>>,
+    mut q_toasts: Query<(Entity, &mut Toast, &mut Text)>,
+) {
+    let Ok(stack) = q_stack.get_single() else {
+        return;
+    };
+    // Collapse repeats within this frame before matching them against toasts on screen
+    let mut incoming: Vec<(Severity, String, usize)> = vec![];
+    while let Some(notification) = notify_queue.next() {
+        let Notification {
+            category,
+            severity,
+            message,
+            timestamp,
+            source,
+            position,
+        } = notification;
+        let mut context = String::new();
+        if let Some(position) = position {
+            context += &format!(" at {}", position);
+        }
+        if let Some(source) = source {
+            context += &format!(" from {}", source);
+        }
+
+        let rule = filter.rule(category);
+        let limited = last_shown
+            .get(&category)
+            .is_some_and(|last| timestamp - last < rule.min_interval);
+        if rule.muted || limited {
+            debug!("[{:?}] {}{} (suppressed)", category, message, context);
+            continue;
+        }
+        last_shown.insert(category, timestamp);
+        match severity {
+            Severity::Info => info!("[{:?}] {}{}", category, message, context),
+            Severity::Warning => warn!("[{:?}] {}{}", category, message, context),
+            Severity::Error => error!("[{:?}] {}{}", category, message, context),
+        }
+        match incoming
+            .iter_mut()
+            .find(|(s, m, _)| *s == severity && *m == message)
+        {
+            Some((.., count)) => *count += 1,
+            None => incoming.push((severity, message, 1)),
+        }
+    }
+
+    let mut fresh = vec![];
+    for (severity, message, count) in incoming {
+        match q_toasts
+            .iter_mut()
+            .find(|(_, toast, _)| toast.severity == severity && toast.message == message)
+        {
+            Some((_, mut toast, mut text)) => {
+                toast.count += count;
+                toast.timer.reset();
+                text.sections[0].value = toast.label();
+            }
+            None => fresh.push((severity, message, count)),
+        }
     }
+
+    // Spawns only land at the end of the frame, so trim the new toasts here: only the newest fit
+    // if more arrived than can be shown, then the longest untouched old ones make room for them
+    let fresh = fresh.split_off(fresh.len().saturating_sub(TOAST_MAX_VISIBLE));
+    let excess = (q_toasts.iter().count() + fresh.len()).saturating_sub(TOAST_MAX_VISIBLE);
+    if excess > 0 {
+        let mut toasts = q_toasts.iter().collect::<Vec<_>>();
+        toasts.sort_by_key(|(_, toast, _)| std::cmp::Reverse(toast.timer.elapsed()));
+        toasts
+            .into_iter()
+            .take(excess)
+            .for_each(|(entity, ..)| commands.entity(entity).despawn_recursive());
+    }
+
+    for (severity, message, count) in fresh {
+        let toast = Toast {
+            severity,
+            message,
+            count,
+            timer: Timer::from_seconds(TOAST_DURATION, TimerMode::Once),
+        };
+        let toast_entity = commands
+            .spawn((
+                TextBundle::from_section(toast.label(), TextStyle::default())
+                    .with_style(Style {
+                        padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                        ..default()
+                    })
+                    .with_background_color(severity.color()),
+                toast,
+            ))
+            .id();
+        commands.entity(stack).add_child(toast_entity);
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_toasts: Query<(Entity, &mut Toast)>,
+) {
+    q_toasts.iter_mut().for_each(|(entity, mut toast)| {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    })
 }
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+
+use crate::{
+    constants::{BUILD_PHASE_DURATION, CALL_EARLY_BONUS, WAVE_DURATION},
+    currency::{Currency, Ledger, LedgerCategory},
+    game::GameState,
+    locale::Locale,
+    select_tile::ResourceType,
+    ui::{CallEarlyButton, WaveCounter},
+    util::{Category, Notification, NotifyQueue},
+};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(WaveState::default())
+            .add_event::<BuildPhaseEnded>()
+            .add_systems(
+                Update,
+                (call_wave_early, advance_phase, update_wave_hud)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Build,
+    Wave,
+}
+
+#[derive(Resource, Debug)]
+pub struct WaveState {
+    pub wave: usize,
+    pub phase: Phase,
+    pub timer: Timer,
+}
+
+impl Default for WaveState {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            phase: Phase::Build,
+            timer: Timer::from_seconds(BUILD_PHASE_DURATION, TimerMode::Once),
+        }
+    }
+}
+
+/// Sent when the build phase is over, right before wave `wave` starts.
+#[derive(Event, Debug)]
+pub struct BuildPhaseEnded {
+    pub wave: usize,
+}
+
+impl WaveState {
+    /// Wood paid for starting the next wave now, or `None` outside the build phase.
+    pub fn call_early_bonus(&self) -> Option<usize> {
+        (self.phase == Phase::Build)
+            .then(|| (self.timer.remaining_secs() * CALL_EARLY_BONUS) as usize)
+    }
+}
+
+fn start_wave(
+    wave_state: &mut WaveState,
+    build_phase_ended: &mut EventWriter<BuildPhaseEnded>,
+    notify_queue: &mut NotifyQueue,
+    locale: &Locale,
+) {
+    wave_state.wave += 1;
+    wave_state.phase = Phase::Wave;
+    // Waves have no enemies yet, so they just run for a fixed time
+    wave_state.timer = Timer::from_seconds(WAVE_DURATION, TimerMode::Once);
+    build_phase_ended.send(BuildPhaseEnded {
+        wave: wave_state.wave,
+    });
+    notify_queue.push(Notification::new(
+        Category::Wave,
+        locale.fmt("wave-started", &[("wave", &locale.number(wave_state.wave))]),
+    ));
+}
+
+fn call_wave_early(
+    q_button: Query<&Interaction, (Changed<Interaction>, With<CallEarlyButton>)>,
+    mut wave_state: ResMut<WaveState>,
+    mut currency: ResMut<Currency>,
+    mut ledger: ResMut<Ledger>,
+    mut build_phase_ended: EventWriter<BuildPhaseEnded>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+) {
+    if !q_button
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        return;
+    }
+    let Some(bonus) = wave_state.call_early_bonus() else {
+        return;
+    };
+    currency.wood += bonus;
+    ledger.record(
+        LedgerCategory::EarlyCall,
+        ResourceType::Wood,
+        bonus as isize,
+    );
+    notify_queue.push(Notification::new(
+        Category::Wave,
+        locale.fmt(
+            "wave-called-early",
+            &[
+                ("wave", &locale.number(wave_state.wave + 1)),
+                ("amount", &locale.number(bonus)),
+            ],
+        ),
+    ));
+    start_wave(
+        &mut wave_state,
+        &mut build_phase_ended,
+        &mut notify_queue,
+        &locale,
+    );
+}
+
+#[allow(clippy::type_complexity)]
+fn update_wave_hud(
+    wave_state: Res<WaveState>,
+    locale: Res<Locale>,
+    mut q_counter: Query<&mut Text, With<WaveCounter>>,
+    mut q_button: Query<(&mut Style, &Children), With<CallEarlyButton>>,
+    mut q_text: Query<&mut Text, Without<WaveCounter>>,
+) {
+    let seconds = locale.number(wave_state.timer.remaining_secs().ceil());
+    if let Ok(mut text) = q_counter.get_single_mut() {
+        let value = match wave_state.phase {
+            Phase::Build => locale.fmt(
+                "wave-countdown",
+                &[
+                    ("wave", &locale.number(wave_state.wave + 1)),
+                    ("seconds", &seconds),
+                ],
+            ),
+            Phase::Wave => locale.fmt(
+                "wave-running",
+                &[
+                    ("wave", &locale.number(wave_state.wave)),
+                    ("seconds", &seconds),
+                ],
+            ),
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+
+    let Ok((mut style, children)) = q_button.get_single_mut() else {
+        return;
+    };
+    let bonus = wave_state.call_early_bonus();
+    let display = match bonus {
+        Some(_) => Display::Flex,
+        None => Display::None,
+    };
+    if style.display != display {
+        style.display = display;
+    }
+    let Some(bonus) = bonus else {
+        return;
+    };
+    let value = locale.fmt("wave-call-early", &[("amount", &locale.number(bonus))]);
+    let mut texts = q_text.iter_many_mut(children);
+    while let Some(mut text) = texts.fetch_next() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+fn advance_phase(
+    mut wave_state: ResMut<WaveState>,
+    mut build_phase_ended: EventWriter<BuildPhaseEnded>,
+    mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
+    time: Res<Time>,
+) {
+    if !wave_state.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+    match wave_state.phase {
+        Phase::Build => start_wave(
+            &mut wave_state,
+            &mut build_phase_ended,
+            &mut notify_queue,
+            &locale,
+        ),
+        Phase::Wave => {
+            wave_state.phase = Phase::Build;
+            wave_state.timer = Timer::from_seconds(BUILD_PHASE_DURATION, TimerMode::Once);
+            notify_queue.push(Notification::new(
+                Category::Wave,
+                locale.fmt("wave-cleared", &[("wave", &locale.number(wave_state.wave))]),
+            ));
+        }
+    }
+}
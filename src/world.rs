@@ -4,64 +4,117 @@ use bevy_rapier3d::prelude::*;
 use itertools::iproduct;
 
 use crate::{
-    constants::{TEXTURE_MAP, TILE_SIZE, WORLD_SIZE},
+    constants::{TILE_SIZE, TILE_SPACING, WORLD_SIZE},
+    game::GameState,
+    input_map::{ActionInput, InputAction},
+    locale::Locale,
+    research::Research,
     select_tile::*,
-    util::NotifyQueue,
+    util::{Category, Notification, NotifyQueue},
 };
 
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_world)
-            .add_event::<TileHighlightEvent>()
+        app.add_event::<TileHighlightEvent>()
+            .add_systems(
+                OnEnter(GameState::Loading),
+                (despawn_world, spawn_world).chain(),
+            )
+            .add_systems(OnExit(GameState::Summary), despawn_world)
             .add_systems(
                 Update,
-                (log_hover.run_if(on_event::<TileHighlightEvent>()),),
+                (
+                    (track_hover, log_hover).run_if(on_event::<TileHighlightEvent>()),
+                    draw_tile_outlines,
+                    draw_tower_ranges,
+                )
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
 
 #[derive(Component, Debug)]
-pub struct Position(Vec3);
+pub struct Position(pub(crate) Vec3);
 
 #[derive(Component)]
 pub struct Tile;
 
+/// Marks the tile under the pointer.
+#[derive(Component)]
+pub struct Hovered;
+
 #[derive(Event, Debug)]
-pub struct TileHighlightEvent(Entity);
+pub struct TileHighlightEvent {
+    target: Entity,
+    hovered: bool,
+}
 
 impl From<ListenerInput<Pointer<Over>>> for TileHighlightEvent {
     fn from(value: ListenerInput<Pointer<Over>>) -> Self {
-        TileHighlightEvent(value.target)
+        TileHighlightEvent {
+            target: value.target,
+            hovered: true,
+        }
+    }
+}
+
+impl From<ListenerInput<Pointer<Out>>> for TileHighlightEvent {
+    fn from(value: ListenerInput<Pointer<Out>>) -> Self {
+        TileHighlightEvent {
+            target: value.target,
+            hovered: false,
+        }
     }
 }
 
+/// Center of the tile footprint; tile scenes are anchored at a corner.
+pub fn tile_center(translation: Vec3) -> Vec3 {
+    translation - Vec3::new(TILE_SIZE, 0.0, TILE_SIZE)
+}
+
+/// Removes the previous game's tiles and light before a new world is spawned.
+#[allow(clippy::type_complexity)]
+fn despawn_world(
+    mut commands: Commands,
+    q_world: Query<Entity, Or<(With<Tile>, With<DirectionalLight>)>>,
+) {
+    q_world
+        .iter()
+        .for_each(|entity| commands.entity(entity).despawn_recursive());
+}
+
 fn spawn_world(mut commands: Commands, asset_server: Res<AssetServer>) {
     // TODO: Randomize
-    let centers = [Vec3::new(2.0, 0.0, 2.0), Vec3::new(8.0, 0.0, 8.0)];
+    let centers = [
+        (Vec3::new(2.0, 0.0, 2.0), ResourceType::Wood),
+        (Vec3::new(8.0, 0.0, 8.0), ResourceType::Wood),
+        (Vec3::new(14.0, 0.0, 4.0), ResourceType::Stone),
+    ];
     const RADIUS: f32 = 2.0;
 
     // Switch to hexagon three axis coordinate space
     for (i, j) in iproduct!(0..WORLD_SIZE, 0..WORLD_SIZE) {
         // Convert to hexagonal coordinates
         let pos = Vec3::new(i as f32, 0.0, j as f32);
-        let tile_type = if centers.iter().any(|center| center.distance(pos) < RADIUS) {
-            // Add more resources
-            TileType::Resource(ResourceType::Wood)
-        } else {
-            TileType::Floor
-        };
+        let tile_type = centers
+            .iter()
+            .find(|(center, _)| center.distance(pos) < RADIUS)
+            .map_or(TileType::Floor, |(_, resource)| {
+                TileType::Resource(*resource)
+            });
         let collider_height = tile_type.get_collider_height();
         commands
             .spawn((
                 SceneBundle {
-                    scene: asset_server.load(TEXTURE_MAP.get(&tile_type).cloned().unwrap()),
-                    transform: Transform::from_translation(pos * 16.0),
+                    scene: asset_server.load(tile_type.texture()),
+                    transform: Transform::from_translation(pos * TILE_SPACING),
                     ..default()
                 },
                 Tile,
                 Selected(false),
+                Invested::default(),
                 tile_type,
             ))
             .insert(Position(pos))
@@ -91,6 +144,7 @@ fn spawn_world(mut commands: Commands, asset_server: Res<AssetServer>) {
                     )))
                     .insert((
                         On::<Pointer<Over>>::send_event::<TileHighlightEvent>(),
+                        On::<Pointer<Out>>::send_event::<TileHighlightEvent>(),
                         On::<Pointer<Click>>::send_event::<TileSelectEvent>(),
                     ));
             });
@@ -110,13 +164,107 @@ fn spawn_world(mut commands: Commands, asset_server: Res<AssetServer>) {
 fn log_hover(
     mut hovered: EventReader<TileHighlightEvent>,
     q_collider: Query<&Parent>,
-    q_position: Query<&Position>,
+    q_position: Query<(&Position, &Transform)>,
     mut notify_queue: ResMut<NotifyQueue>,
+    locale: Res<Locale>,
 ) {
-    // TODO: slight highlighting of tiles
     hovered
         .read()
-        .filter_map(|ev| q_collider.get(ev.0).ok())
-        .filter_map(|p| q_position.get(p.get()).ok())
-        .for_each(|Position(pos)| notify_queue.push(format!("Tile selected: {:?}", pos)))
+        .filter(|ev| ev.hovered)
+        .filter_map(|ev| q_collider.get(ev.target).ok())
+        .filter_map(|p| Some((p.get(), q_position.get(p.get()).ok()?)))
+        .for_each(|(tile, (Position(pos), transform))| {
+            notify_queue.push(
+                Notification::new(
+                    Category::Hover,
+                    locale.fmt(
+                        "hover-tile",
+                        &[("x", &locale.number(pos.x)), ("z", &locale.number(pos.z))],
+                    ),
+                )
+                .with_source(tile)
+                .at(transform.translation),
+            )
+        })
+}
+
+fn track_hover(
+    mut commands: Commands,
+    mut hovered: EventReader<TileHighlightEvent>,
+    q_collider: Query<&Parent>,
+) {
+    hovered.read().for_each(|ev| {
+        let Ok(parent) = q_collider.get(ev.target) else {
+            return;
+        };
+        match ev.hovered {
+            true => commands.entity(parent.get()).insert(Hovered),
+            false => commands.entity(parent.get()).remove::<Hovered>(),
+        };
+    })
+}
+
+fn draw_tile_outlines(
+    mut gizmos: Gizmos,
+    q_tiles: Query<(&Transform, &Selected, Has<Hovered>), With<Tile>>,
+) {
+    q_tiles
+        .iter()
+        .filter(|(_, selected, hovered)| selected.0 || *hovered)
+        .for_each(|(transform, selected, hovered)| {
+            let center = tile_center(transform.translation);
+            let mut outline = |radius: f32, height: f32, color: Color| {
+                gizmos.linestrip(
+                    (0..=6).map(|i| {
+                        let angle = i as f32 * std::f32::consts::FRAC_PI_3;
+                        center
+                            + Vec3::new(angle.cos(), 0.0, angle.sin()) * radius
+                            + Vec3::Y * height
+                    }),
+                    color,
+                )
+            };
+            if selected.0 {
+                outline(TILE_SIZE + 0.5, 0.2, Color::srgb(1.0, 0.8, 0.0));
+                outline(TILE_SIZE + 0.8, 0.2, Color::srgb(1.0, 0.8, 0.0));
+            }
+            if hovered {
+                outline(TILE_SIZE + 0.2, 0.4, Color::WHITE);
+            }
+        })
+}
+
+/// Rings for the range of hovered and selected towers, or every tower while the modifier is
+/// held. Hovered and selected towers also get a fainter ring for their next level.
+fn draw_tower_ranges(
+    mut gizmos: Gizmos,
+    inputs: ActionInput,
+    research: Res<Research>,
+    q_tiles: Query<(&Transform, &TileType, &Selected, Has<Hovered>), With<Tile>>,
+) {
+    let show_all = inputs.pressed(InputAction::ShowRanges);
+    q_tiles
+        .iter()
+        .filter(|(_, _, selected, hovered)| show_all || selected.0 || *hovered)
+        .for_each(|(transform, tile_type, selected, hovered)| {
+            let Some(range) = tile_type.range() else {
+                return;
+            };
+            let center = tile_center(transform.translation) + Vec3::Y * 0.3;
+            gizmos.circle(center, Dir3::Y, range, Color::srgb(1.0, 0.3, 0.2));
+            if !(selected.0 || hovered) {
+                return;
+            }
+            if let Some(next_range) = tile_type
+                .available_action(&Action::MakeTile, &research)
+                .and_then(|next| next.range())
+            {
+                gizmos.circle(
+                    center,
+                    Dir3::Y,
+                    next_range,
+                    Color::srgba(1.0, 0.3, 0.2, 0.35),
+                );
+            }
+        })
 }